@@ -0,0 +1,33 @@
+use std::{env, fmt::Write, fs, path::Path};
+
+#[path = "src/content_files.rs"]
+mod content_files;
+
+use content_files::{collect_paths, CONTENT_DIRS, CONTENT_FILES};
+
+/// With the `embed-content` feature, lists every content file for
+/// `include_bytes!` so the binary runs without any loose files.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/content_files.rs");
+    if env::var_os("CARGO_FEATURE_EMBED_CONTENT").is_none() {
+        return;
+    }
+
+    let content_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let content_dir = Path::new(&content_dir);
+    for path in CONTENT_FILES.iter().chain(CONTENT_DIRS) {
+        println!("cargo:rerun-if-changed={}", content_dir.join(path).display());
+    }
+
+    let paths = collect_paths(content_dir).unwrap_or_else(|message| panic!("{}", message));
+    let mut embedded = String::from("pub static EMBEDDED_FILES: &[(&str, &[u8])] = &[\n");
+    for path in &paths {
+        let full_path = content_dir.join(path);
+        writeln!(embedded, "    ({:?}, include_bytes!({:?})),", path, full_path.to_str().unwrap()).unwrap();
+    }
+    embedded.push_str("];\n");
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded_content.rs");
+    fs::write(out_path, embedded).unwrap();
+}
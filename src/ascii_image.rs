@@ -0,0 +1,123 @@
+use macroquad::{color::Color, texture::Image};
+
+/// Characters from empty to full, picked by how bright a cell is.
+const LUMINANCE_RAMP: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+/// Pixels less opaque than this are treated as empty.
+const MIN_ALPHA: f32 = 0.5;
+const CUBE_LEVELS: usize = 6;
+
+/// The 6×6×6 colour cube of 256 colour terminals. Image colours are snapped
+/// to it so cells can keep pointing at `'static` colours.
+pub static COLOR_CUBE: [Color; CUBE_LEVELS * CUBE_LEVELS * CUBE_LEVELS] = color_cube();
+
+const fn color_cube() -> [Color; CUBE_LEVELS * CUBE_LEVELS * CUBE_LEVELS] {
+    let mut colors = [Color::new(0.0, 0.0, 0.0, 1.0); CUBE_LEVELS * CUBE_LEVELS * CUBE_LEVELS];
+    let max_level = (CUBE_LEVELS - 1) as f32;
+    let mut index = 0;
+    while index < colors.len() {
+        let red = (index / (CUBE_LEVELS * CUBE_LEVELS)) as f32 / max_level;
+        let green = (index / CUBE_LEVELS % CUBE_LEVELS) as f32 / max_level;
+        let blue = (index % CUBE_LEVELS) as f32 / max_level;
+        colors[index] = Color::new(red, green, blue, 1.0);
+        index += 1;
+    }
+    colors
+}
+
+/// The colour cube entry closest to `color`.
+pub fn cube_color(color: Color) -> &'static Color {
+    let level = |channel: f32| (channel.clamp(0.0, 1.0) * (CUBE_LEVELS - 1) as f32).round() as usize;
+    &COLOR_CUBE[level(color.r) * CUBE_LEVELS * CUBE_LEVELS + level(color.g) * CUBE_LEVELS + level(color.b)]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtStyle {
+    /// Characters from a luminance ramp, drawn in the theme's colour.
+    Ramp,
+    /// `▀` and `▄` with the image's colours, two pixels to a cell.
+    HalfBlock,
+}
+
+/// One cell of converted art. Colours are `None` where the theme's own should be used.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArtCell {
+    pub char: char,
+    pub foreground: Option<&'static Color>,
+    pub background: Option<&'static Color>,
+}
+
+/// Decodes a PNG, JPEG or TGA image.
+pub fn decode_image(bytes: &[u8]) -> Result<Image, String> {
+    Image::from_file_with_format(bytes, None).map_err(|error| error.to_string())
+}
+
+/// The most cells `image` can take within `max_columns` by `max_rows` while
+/// keeping its proportions, with cells twice as tall as they are wide.
+pub fn fit_size(image: &Image, max_columns: usize, max_rows: usize) -> (usize, usize) {
+    if image.width == 0 || image.height == 0 {
+        return (0, 0);
+    }
+    // Width over height of the image, measured in cells.
+    let aspect = image.width as f32 / image.height as f32 * 2.0;
+    let columns = (max_columns as f32).min(max_rows as f32 * aspect).max(1.0);
+    (columns.round() as usize, (columns / aspect).round().max(1.0) as usize)
+}
+
+/// Average colour of the pixels in the given fractions of the image, with
+/// alpha as the share of them that are opaque.
+fn average(image: &Image, left: f32, top: f32, right: f32, bottom: f32) -> Color {
+    let (width, height) = (image.width as f32, image.height as f32);
+    let x_range = (left * width) as u32..((right * width).ceil() as u32).clamp((left * width) as u32 + 1, image.width as u32);
+    let y_range = (top * height) as u32..((bottom * height).ceil() as u32).clamp((top * height) as u32 + 1, image.height as u32);
+
+    let (mut sum, mut opaque, mut count) = ([0f32; 3], 0f32, 0f32);
+    for y in y_range {
+        for x in x_range.clone() {
+            let pixel = image.get_pixel(x, y);
+            count += 1.0;
+            if pixel.a >= MIN_ALPHA {
+                sum[0] += pixel.r;
+                sum[1] += pixel.g;
+                sum[2] += pixel.b;
+                opaque += 1.0;
+            }
+        }
+    }
+
+    match opaque > 0.0 {
+        true => Color::new(sum[0] / opaque, sum[1] / opaque, sum[2] / opaque, opaque / count),
+        false => Color::new(0.0, 0.0, 0.0, 0.0),
+    }
+}
+
+fn luminance(color: Color) -> f32 {
+    0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+/// Converts `image` to `columns` by `rows` cells, stretching it to fit.
+pub fn image_to_art(image: &Image, columns: usize, rows: usize, style: ArtStyle) -> Vec<Vec<ArtCell>> {
+    let (column_width, row_height) = (1.0 / columns as f32, 1.0 / rows as f32);
+
+    (0..rows).map(|row| (0..columns).map(|column| {
+        let (left, top) = (column as f32 * column_width, row as f32 * row_height);
+        match style {
+            ArtStyle::Ramp => {
+                let color = average(image, left, top, left + column_width, top + row_height);
+                let brightness = luminance(color) * color.a;
+                let index = (brightness * (LUMINANCE_RAMP.len() - 1) as f32).round() as usize;
+                ArtCell { char: LUMINANCE_RAMP[index], foreground: None, background: None }
+            }
+            ArtStyle::HalfBlock => {
+                let middle = top + row_height / 2.0;
+                let upper = average(image, left, top, left + column_width, middle);
+                let lower = average(image, left, middle, left + column_width, top + row_height);
+                match (upper.a >= MIN_ALPHA, lower.a >= MIN_ALPHA) {
+                    (true, true) => ArtCell { char: '▀', foreground: Some(cube_color(upper)), background: Some(cube_color(lower)) },
+                    (true, false) => ArtCell { char: '▀', foreground: Some(cube_color(upper)), background: None },
+                    (false, true) => ArtCell { char: '▄', foreground: Some(cube_color(lower)), background: None },
+                    (false, false) => ArtCell { char: ' ', foreground: None, background: None },
+                }
+            }
+        }
+    }).collect()).collect()
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    content,
+    error::{Error, Result},
+    models::TerminalState,
+    projects::{parse_project_entries, projects_from_entries},
+};
+
+pub const MANIFEST_PATH: &str = "assets.json";
+/// Copy of the manifest built into the binary, used when `assets.json` can't be loaded.
+const BUILT_IN_MANIFEST: &str = include_str!("../assets.json");
+
+/// Slot a loaded asset fills, and the key the rest of the code looks it up by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum AssetKey {
+    TerminalFont,
+    ResumeFont,
+    ResumeBoldFont,
+    ResumeItalicFont,
+    ResumeBoldItalicFont,
+    Projects,
+}
+
+/// How an asset's bytes are decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    Font,
+    ProjectList,
+}
+
+impl AssetKey {
+    /// The kind of asset that fits this slot.
+    fn kind(self) -> AssetKind {
+        match self {
+            AssetKey::Projects => AssetKind::ProjectList,
+            _ => AssetKind::Font,
+        }
+    }
+}
+
+/// One line of `assets.json`. Optional assets that fail to load are left out
+/// without asking, required ones ask to retry or skip.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AssetEntry {
+    pub key: AssetKey,
+    pub path: String,
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl AssetEntry {
+    pub fn kind(&self) -> AssetKind {
+        self.key.kind()
+    }
+}
+
+/// Fonts loaded from the manifest, by key. The project list is decoded
+/// straight into `TerminalState::projects` instead.
+#[derive(Default)]
+pub struct AssetRegistry {
+    fonts: HashMap<AssetKey, Font>,
+}
+
+impl AssetRegistry {
+    pub fn font(&self, key: AssetKey) -> Option<&Font> {
+        self.fonts.get(&key)
+    }
+}
+
+pub fn parse_manifest(path: &str, bytes: &[u8]) -> Result<Vec<AssetEntry>> {
+    serde_json::from_slice(bytes).map_err(|source| Error::Parse { path: path.to_string(), source })
+}
+
+/// The asset manifest, falling back to the built-in copy when `assets.json` is missing or broken.
+pub async fn load_manifest() -> Vec<AssetEntry> {
+    let loaded = content::load_file(MANIFEST_PATH).await
+        .map_err(|source| Error::Load { path: MANIFEST_PATH.to_string(), source })
+        .and_then(|bytes| parse_manifest(MANIFEST_PATH, &bytes));
+
+    match loaded {
+        Ok(entries) => entries,
+        Err(error) => {
+            warn!("{}, using the built-in asset manifest", error);
+            built_in_manifest()
+        }
+    }
+}
+
+pub fn built_in_manifest() -> Vec<AssetEntry> {
+    parse_manifest(MANIFEST_PATH, BUILT_IN_MANIFEST.as_bytes()).expect("Built-in asset manifest is invalid")
+}
+
+/// Decodes a loaded asset and puts it in its slot.
+pub fn apply_asset(terminal_state: &mut TerminalState, entry: &AssetEntry, bytes: &[u8]) -> Result<()> {
+    match entry.kind() {
+        AssetKind::Font => {
+            let font = load_ttf_font_from_bytes(bytes).map_err(|source| Error::Font { path: entry.path.clone(), source })?;
+            terminal_state.assets.fonts.insert(entry.key, font);
+        }
+        AssetKind::ProjectList => {
+            let project_entries = parse_project_entries(&entry.path, bytes)?;
+            terminal_state.projects = projects_from_entries(&entry.path, &project_entries)?;
+            terminal_state.project_entries = project_entries;
+        }
+    }
+    Ok(())
+}
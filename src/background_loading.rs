@@ -1,104 +1,182 @@
-use std::{sync::{Arc, Mutex}, thread};
+use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+use macroquad::miniquad::fs;
+
+use crate::{content, error::{Error, Result}};
 
-use macroquad::{experimental::coroutines::start_coroutine, file::load_file, miniquad::fs::Response};
 pub enum LoadingState {
     Loading,
-    Finished(Response)
+    Finished(Result<Vec<u8>>),
+    /// Nobody is waiting for the file anymore, so it is dropped when it arrives.
+    Cancelled,
 }
 
+/// A file loading without holding up the frame.
 pub trait BackgroundLoadable {
     fn load_file(file_path: String) -> Self;
 
+    /// Whether the file has arrived or failed to.
     fn check_file(&self) -> bool;
 
-    fn get_file(&self) -> Vec<u8>;
-}
-
-pub fn background_load_file(path: &str) {
-    let new_path = path.to_string();
-    let coroutine = start_coroutine(async move {
-        load_file(&new_path).await;
-    });
-
-    loop {
-        coroutine.is_done();
-        coroutine.retrieve();
+    /// The file's contents or why it couldn't be loaded, once it is done.
+    fn take_file(&self) -> Option<Result<Vec<u8>>>;
 
-    }
+    fn cancel(&self);
+}
 
+pub struct BackgroundFile {
+    file_loading_state: Arc<Mutex<LoadingState>>,
 }
 
+/// Reads the file on its own thread, since loading from disk blocks.
 #[cfg(not(target_arch = "wasm32"))]
-pub mod background_file {
-    use super::*;
-    pub struct BackgroundFile {
-        pub file_loading_state: Arc<Mutex<LoadingState>>
-    }
+fn spawn_load(file_path: String, on_loaded: impl Fn(fs::Response) + Send + 'static) {
+    std::thread::spawn(move || fs::load_file(&file_path, on_loaded));
+}
+/// The browser fetches the file and calls back on the main thread.
+#[cfg(target_arch = "wasm32")]
+fn spawn_load(file_path: String, on_loaded: impl Fn(fs::Response) + Send + 'static) {
+    fs::load_file(&file_path, on_loaded);
+}
 
-    impl BackgroundLoadable for BackgroundFile {
-
-        fn load_file(file_path: String) -> Self {
-            let file_reference = Arc::new(Mutex::new(LoadingState::Loading));
-            let thread_file_reference = file_reference.clone();
-            thread::spawn(move || {
-                macroquad::miniquad::fs::load_file(&file_path, move |file_response| {
-                    match thread_file_reference.lock() {
-                        Ok(mut file_reference) => *file_reference = LoadingState::Finished(file_response),
-                        Err(_) => panic!("Failed"),
-                    }
-                })
-            });
-
-            BackgroundFile {
-                file_loading_state: file_reference
-            }
+impl BackgroundLoadable for BackgroundFile {
+    fn load_file(file_path: String) -> Self {
+        // Embedded and bundled files are already in memory.
+        if let Some(result) = content::preloaded_file(&file_path) {
+            let result = result.map_err(|source| Error::Load { path: file_path.clone(), source });
+            return BackgroundFile {
+                file_loading_state: Arc::new(Mutex::new(LoadingState::Finished(result))),
+            };
         }
 
-        fn check_file(&self) -> bool {
-            let file_loading_state = self.file_loading_state.lock().expect("Could not lock file");
-
-            match &(*file_loading_state) {
-                LoadingState::Loading => false,
-                LoadingState::Finished(_) => true,
+        let file_reference = Arc::new(Mutex::new(LoadingState::Loading));
+        let thread_file_reference = file_reference.clone();
+        let error_path = file_path.clone();
+
+        spawn_load(file_path, move |file_response| {
+            let Ok(mut file_loading_state) = thread_file_reference.lock() else {
+                return;
+            };
+            if matches!(*file_loading_state, LoadingState::Loading) {
+                *file_loading_state = LoadingState::Finished(file_response.map_err(|kind| Error::Load {
+                    path: error_path.clone(),
+                    source: macroquad::Error::FileError { kind, path: error_path.clone() },
+                }));
             }
+        });
+
+        BackgroundFile {
+            file_loading_state: file_reference,
         }
+    }
 
-        fn get_file(&self) -> Vec<u8> {
-            let file_loading_state = self.file_loading_state.lock().expect("Could not lock file");
-            match &(*file_loading_state) {
-                LoadingState::Loading => panic!("Should not be loading"),
-                LoadingState::Finished(val) => val.as_ref().expect("Error downloading file").clone(),
+    fn check_file(&self) -> bool {
+        self.file_loading_state.lock().is_ok_and(|state| matches!(*state, LoadingState::Finished(_)))
+    }
 
+    fn take_file(&self) -> Option<Result<Vec<u8>>> {
+        let mut file_loading_state = self.file_loading_state.lock().ok()?;
+        match std::mem::replace(&mut *file_loading_state, LoadingState::Cancelled) {
+            LoadingState::Finished(result) => Some(result),
+            still_loading => {
+                *file_loading_state = still_loading;
+                None
             }
         }
     }
+
+    fn cancel(&self) {
+        if let Ok(mut file_loading_state) = self.file_loading_state.lock() {
+            *file_loading_state = LoadingState::Cancelled;
+        }
+    }
 }
 
-#[cfg(target_arch = "wasm32")]
-pub mod background_file {
-    use super::*;
-    use log::debug;
+/// Loads files in the background by path. Files that loaded are kept until
+/// they are taken or forgotten, so asking for them again doesn't fetch them again.
+#[derive(Default)]
+pub struct BackgroundLoader {
+    pending: HashMap<String, BackgroundFile>,
+    loaded: HashMap<String, Vec<u8>>,
+    failed: HashMap<String, Error>,
+}
 
-    pub struct  BackgroundFile {
-        pub file_path: String,
+impl BackgroundLoader {
+    /// Starts loading `path`, unless it is already loading or was loaded.
+    pub fn request(&mut self, path: &str) {
+        if !self.pending.contains_key(path) && !self.loaded.contains_key(path) && !self.failed.contains_key(path) {
+            self.pending.insert(path.to_string(), BackgroundFile::load_file(path.to_string()));
+        }
     }
 
+    pub fn is_loading(&self, path: &str) -> bool {
+        self.pending.contains_key(path)
+    }
 
-    impl BackgroundLoadable for BackgroundFile {
-        
-        fn load_file(file_path: String) -> Self {
-            debug!("Started loading file: {}", &file_path);
-            BackgroundFile {
-                file_path
+    /// Collects files that finished since the last call. Returns whether any did.
+    pub fn poll(&mut self) -> bool {
+        let finished: Vec<String> = self.pending.iter()
+            .filter(|(_, file)| file.check_file())
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in &finished {
+            let Some(result) = self.pending.remove(path).and_then(|file| file.take_file()) else {
+                continue;
+            };
+            match result {
+                Ok(bytes) => {
+                    self.loaded.insert(path.clone(), bytes);
+                }
+                Err(error) => {
+                    self.failed.insert(path.clone(), error);
+                }
             }
         }
+        !finished.is_empty()
+    }
 
-        fn check_file(&self) -> bool {
-            false
-        }
+    /// The file's contents once it has loaded.
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.loaded.get(path).map(Vec::as_slice)
+    }
 
-        fn get_file(&self) -> Vec<u8> {
-            Vec::new()
+    /// The file's contents once it has loaded, for callers that keep what they
+    /// make of it instead. Requesting the file again loads it again.
+    pub fn take(&mut self, path: &str) -> Option<Vec<u8>> {
+        self.loaded.remove(path)
+    }
+
+    /// Why the file failed to load. The error is only handed out once and
+    /// requesting the file again retries it, so callers skip the file until the
+    /// visitor asks for a retry.
+    pub fn take_error(&mut self, path: &str) -> Option<Error> {
+        self.failed.remove(path)
+    }
+
+    /// Stores a file that was loaded some other way, so requesting it doesn't load it again.
+    pub fn insert(&mut self, path: &str, bytes: Vec<u8>) {
+        self.forget(path);
+        self.loaded.insert(path.to_string(), bytes);
+    }
+
+    /// Drops what is known about `path`, so the next request loads it again.
+    pub fn forget(&mut self, path: &str) {
+        if let Some(file) = self.pending.remove(path) {
+            file.cancel();
         }
+        self.loaded.remove(path);
+        self.failed.remove(path);
+    }
+
+    /// Stops loading every pending file except those in `keep`.
+    pub fn cancel_all_except(&mut self, keep: &[&str]) {
+        self.pending.retain(|path, file| {
+            let kept = keep.contains(&path.as_str());
+            if !kept {
+                file.cancel();
+            }
+            kept
+        });
     }
 }
@@ -0,0 +1,92 @@
+//! Converts a PNG or JPEG into ASCII art for a project's front matter.
+//!
+//! Usage: `cargo run --bin image_to_ascii -- <image> <columns> <rows> [--half-block] [--ansi]`
+//!
+//! The image is fitted inside `columns` by `rows` cells; 19 by 6 fills the
+//! project art panel. `--half-block` uses `▀` and `▄` for twice the vertical
+//! detail and also prints colour masks that pick the nearest theme colour for
+//! each cell. `--ansi` previews the result with its colours in the terminal
+//! instead of printing it ready to paste.
+
+use std::{env, fs, process::ExitCode};
+
+use macroquad::color::Color;
+
+#[path = "../ascii_image.rs"]
+mod ascii_image;
+#[allow(dead_code)]
+#[path = "../themes.rs"]
+mod themes;
+
+use ascii_image::{decode_image, fit_size, image_to_art, ArtCell, ArtStyle};
+use themes::PaletteKey;
+
+/// A cell with its colours as 24-bit ANSI escapes.
+fn ansi_cell(cell: &ArtCell) -> String {
+    let channel = |value: f32| (value * 255.0).round() as u8;
+    let escape = |code: u8, color: &Color| format!("\x1b[{};2;{};{};{}m", code, channel(color.r), channel(color.g), channel(color.b));
+
+    let foreground = cell.foreground.map(|color| escape(38, color)).unwrap_or_default();
+    let background = cell.background.map(|color| escape(48, color)).unwrap_or_default();
+    format!("{}{}{}\x1b[0m", foreground, background, cell.char)
+}
+
+/// Prints a front matter block of `lines`.
+fn print_block(key: &str, lines: impl Iterator<Item = String>) {
+    println!("{}: |", key);
+    for line in lines {
+        println!("  {}", line);
+    }
+}
+
+/// One line of a colour mask per row of art, with a space where the cell has no colour.
+fn mask_lines<'a>(art: &'a [Vec<ArtCell>], color: fn(&ArtCell) -> Option<&'static Color>) -> impl Iterator<Item = String> + 'a {
+    art.iter().map(move |row| row.iter().map(|cell| color(cell).map_or(' ', |color| PaletteKey::nearest(color).char())).collect())
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    let positional: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    let [path, columns, rows] = positional[..] else {
+        return Err(String::from("Usage: image_to_ascii <image> <columns> <rows> [--half-block] [--ansi]"));
+    };
+    let columns: usize = columns.parse().map_err(|_| format!("{} is not a number of columns", columns))?;
+    let rows: usize = rows.parse().map_err(|_| format!("{} is not a number of rows", rows))?;
+
+    let bytes = fs::read(path).map_err(|error| format!("Could not read {}: {}", path, error))?;
+    let image = decode_image(&bytes).map_err(|message| format!("Could not decode {}: {}", path, message))?;
+    let style = match flag("--half-block") {
+        true => ArtStyle::HalfBlock,
+        false => ArtStyle::Ramp,
+    };
+
+    let (fitted_columns, fitted_rows) = fit_size(&image, columns, rows);
+    let art = image_to_art(&image, fitted_columns, fitted_rows, style);
+
+    if flag("--ansi") {
+        for row in &art {
+            println!("{}", row.iter().map(ansi_cell).collect::<String>());
+        }
+    }
+    else {
+        print_block("ascii_art", art.iter().map(|row| row.iter().map(|cell| cell.char).collect()));
+        if art.iter().flatten().any(|cell| cell.foreground.is_some()) {
+            print_block("ascii_art_colors", mask_lines(&art, |cell| cell.foreground));
+        }
+        if art.iter().flatten().any(|cell| cell.background.is_some()) {
+            print_block("ascii_art_backgrounds", mask_lines(&art, |cell| cell.background));
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
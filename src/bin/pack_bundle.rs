@@ -0,0 +1,121 @@
+//! Packs the site's content into one bundle for release builds.
+//!
+//! Usage: `cargo run --release --bin pack_bundle -- [content dir] [output]`
+
+use std::{env, fs, path::{Path, PathBuf}, process::ExitCode};
+
+#[path = "../bundle.rs"]
+mod bundle;
+#[path = "../content_files.rs"]
+mod content_files;
+
+use bundle::{content_hash, Bundle, DEFLATED, MAGIC, STORED};
+use content_files::collect_paths;
+
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// Writes the bundle, deflating each entry that gets smaller for it.
+fn pack(content_dir: &Path, paths: &[String]) -> Result<Vec<u8>, String> {
+    let mut index = Vec::new();
+    let mut data = Vec::new();
+
+    index.extend_from_slice(MAGIC);
+    index.extend_from_slice(&(paths.len() as u32).to_le_bytes());
+
+    for path in paths {
+        let unpacked = fs::read(content_dir.join(path)).map_err(|error| format!("Could not read {}: {}", path, error))?;
+        let deflated = miniz_oxide::deflate::compress_to_vec(&unpacked, COMPRESSION_LEVEL);
+        let (compression, stored) = match deflated.len() < unpacked.len() {
+            true => (DEFLATED, deflated.as_slice()),
+            false => (STORED, unpacked.as_slice()),
+        };
+
+        index.extend_from_slice(&(path.len() as u16).to_le_bytes());
+        index.extend_from_slice(path.as_bytes());
+        index.push(compression);
+        index.extend_from_slice(&content_hash(&unpacked).to_le_bytes());
+        index.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        index.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+        index.extend_from_slice(&(unpacked.len() as u32).to_le_bytes());
+        data.extend_from_slice(stored);
+
+        println!("{:>10} -> {:>10}  {}", unpacked.len(), stored.len(), path);
+    }
+
+    index.extend_from_slice(&data);
+    Ok(index)
+}
+
+/// Reads every entry back, so a broken bundle never gets deployed.
+fn verify(content_dir: &Path, paths: &[String], packed: Vec<u8>) -> Result<(), String> {
+    let bundle = Bundle::parse(packed)?;
+    if bundle.paths().count() != paths.len() {
+        return Err(String::from("The bundle index has a different number of files than were packed"));
+    }
+    for path in paths {
+        let unpacked = bundle.read(path).ok_or(format!("{} is missing from the bundle", path))??;
+        let original = fs::read(content_dir.join(path)).map_err(|error| format!("Could not read {}: {}", path, error))?;
+        if unpacked != original {
+            return Err(format!("{} does not unpack to the original file", path));
+        }
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let mut args = env::args().skip(1);
+    let content_dir = PathBuf::from(args.next().unwrap_or(".".to_string()));
+    let output = PathBuf::from(args.next().unwrap_or("content.bundle".to_string()));
+
+    let paths = collect_paths(&content_dir)?;
+    let packed = pack(&content_dir, &paths)?;
+    fs::write(&output, &packed).map_err(|error| format!("Could not write {}: {}", output.display(), error))?;
+    verify(&content_dir, &paths, packed)?;
+
+    println!("Packed {} files into {}", paths.len(), output.display());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_files_read_back_unchanged() {
+        let content_dir = env::temp_dir().join(format!("pack_bundle_test_{}", std::process::id()));
+        fs::create_dir_all(content_dir.join("projects")).unwrap();
+        let files: [(&str, Vec<u8>); 3] = [
+            ("resume.md", b"# Resume\n\nSome text that repeats. ".repeat(40)),
+            ("projects.json", b"[\"pong.md\"]".to_vec()),
+            ("projects/pong.md", vec![0, 255, 7]),
+        ];
+        for (path, bytes) in &files {
+            fs::write(content_dir.join(path), bytes).unwrap();
+        }
+
+        let paths = collect_paths(&content_dir).unwrap();
+        let packed = pack(&content_dir, &paths).unwrap();
+        let packed_len = packed.len();
+        let bundle = Bundle::parse(packed.clone()).unwrap();
+        let result = verify(&content_dir, &paths, packed);
+        fs::remove_dir_all(&content_dir).unwrap();
+
+        result.unwrap();
+        assert_eq!(bundle.paths().count(), files.len());
+        for (path, bytes) in &files {
+            assert_eq!(&bundle.read(path).unwrap().unwrap(), bytes);
+        }
+        // The repeated text is worth deflating, so the bundle is smaller than its files.
+        assert!(packed_len < files.iter().map(|(_, bytes)| bytes.len()).sum());
+    }
+}
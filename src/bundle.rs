@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+pub const MAGIC: &[u8; 8] = b"CMBNDL01";
+pub const STORED: u8 = 0;
+pub const DEFLATED: u8 = 1;
+
+/// FNV-1a over the unpacked contents, checked when an entry is read.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+struct BundleEntry {
+    compression: u8,
+    hash: u64,
+    offset: usize,
+    stored_len: usize,
+    unpacked_len: usize,
+}
+
+impl BundleEntry {
+    /// Where the entry's data ends, if that fits in a `usize`.
+    fn end(&self) -> Option<usize> {
+        self.offset.checked_add(self.stored_len)
+    }
+}
+
+/// The site's files packed into one archive, so the browser fetches a single
+/// file. Written by the `pack_bundle` binary.
+///
+/// Layout, integers little-endian:
+/// - `MAGIC`
+/// - entry count, `u32`
+/// - per entry: path length `u16`, path, compression `u8`, content hash `u64`,
+///   data offset `u32`, stored length `u32`, unpacked length `u32`
+/// - entry data, offsets counted from the end of the index
+pub struct Bundle {
+    entries: HashMap<String, BundleEntry>,
+    data: Vec<u8>,
+}
+
+/// Reads through the index, failing on anything cut short.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let taken = self.position.checked_add(len)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or("bundle index is cut short")?;
+        self.position += len;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+impl Bundle {
+    /// Reads the index. Entry data is only checked and unpacked when read.
+    pub fn parse(mut bytes: Vec<u8>) -> Result<Bundle, String> {
+        let mut reader = Reader { bytes: &bytes, position: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("not a content bundle".to_string());
+        }
+
+        let count = reader.u32()?;
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            let path_len = reader.u16()? as usize;
+            let path = String::from_utf8(reader.take(path_len)?.to_vec()).map_err(|_| "bundle path is not valid UTF-8")?;
+            let entry = BundleEntry {
+                compression: reader.u8()?,
+                hash: reader.u64()?,
+                offset: reader.u32()? as usize,
+                stored_len: reader.u32()? as usize,
+                unpacked_len: reader.u32()? as usize,
+            };
+            entries.insert(path, entry);
+        }
+
+        let index_len = reader.position;
+        let data_len = bytes.len() - index_len;
+        if let Some((path, _)) = entries.iter().find(|(_, entry)| entry.end().is_none_or(|end| end > data_len)) {
+            return Err(format!("{} runs past the end of the bundle", path));
+        }
+
+        bytes.drain(..index_len);
+        Ok(Bundle { entries, data: bytes })
+    }
+
+    /// Paths of every file in the bundle.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|path| path.as_str())
+    }
+
+    /// The unpacked contents of `path`, or `None` if the bundle doesn't have it.
+    pub fn read(&self, path: &str) -> Option<Result<Vec<u8>, String>> {
+        let entry = self.entries.get(path)?;
+        let Some(stored) = entry.end().and_then(|end| self.data.get(entry.offset..end)) else {
+            return Some(Err(format!("{} runs past the end of the bundle", path)));
+        };
+
+        let unpacked = match entry.compression {
+            STORED => Ok(stored.to_vec()),
+            DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(stored, entry.unpacked_len)
+                .map_err(|error| format!("{} could not be unpacked: {}", path, error)),
+            other => Err(format!("{} uses unknown compression {}", path, other)),
+        };
+
+        Some(unpacked.and_then(|bytes| match content_hash(&bytes) == entry.hash {
+            true => Ok(bytes),
+            false => Err(format!("{} does not match its content hash", path)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An index with one entry, followed by `data`.
+    fn bundle_bytes(path: &str, compression: u8, hash: u64, offset: u32, stored_len: u32, unpacked_len: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(path.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.push(compression);
+        bytes.extend_from_slice(&hash.to_le_bytes());
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&stored_len.to_le_bytes());
+        bytes.extend_from_slice(&unpacked_len.to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_entries() {
+        let text = b"# hello\n".repeat(20);
+        let deflated = miniz_oxide::deflate::compress_to_vec(&text, 6);
+        let bytes = bundle_bytes("resume.md", DEFLATED, content_hash(&text), 0, deflated.len() as u32, text.len() as u32, &deflated);
+        let bundle = Bundle::parse(bytes).unwrap();
+        assert_eq!(bundle.read("resume.md").unwrap().unwrap(), text);
+        assert!(bundle.read("missing.md").is_none());
+
+        let bytes = bundle_bytes("a.txt", STORED, content_hash(b"abc"), 0, 3, 3, b"abc");
+        assert_eq!(Bundle::parse(bytes).unwrap().read("a.txt").unwrap().unwrap(), b"abc");
+    }
+
+    #[test]
+    fn rejects_broken_bundles() {
+        assert!(Bundle::parse(b"NOTABNDL\0\0\0\0".to_vec()).is_err());
+        let bytes = bundle_bytes("a.txt", STORED, content_hash(b"abc"), 0, 3, 3, b"abc");
+        assert!(Bundle::parse(bytes[..bytes.len() - 5].to_vec()).is_err());
+    }
+
+    #[test]
+    fn rejects_entries_past_the_end() {
+        let bytes = bundle_bytes("a.txt", STORED, 0, 2, 3, 3, b"abc");
+        assert!(Bundle::parse(bytes).is_err());
+        let bytes = bundle_bytes("a.txt", STORED, 0, u32::MAX, u32::MAX, 3, b"abc");
+        assert!(Bundle::parse(bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_entries_that_dont_match_their_hash() {
+        let bytes = bundle_bytes("a.txt", STORED, content_hash(b"abd"), 0, 3, 3, b"abc");
+        assert!(Bundle::parse(bytes).unwrap().read("a.txt").unwrap().is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashMap, fmt, fs, path::Path, process::ExitCode};
+
+use fontdue::{Font, FontSettings};
+use serde_json::Value;
+
+use crate::{
+    ascii_image::decode_image,
+    assets::{built_in_manifest, parse_manifest, AssetEntry, AssetKey, AssetKind, MANIFEST_PATH},
+    front_matter::{entry_markdown, merge_project, split_front_matter},
+    markdown_renderer::{image_paths, unsupported_constructs},
+    models::ProjectInfo,
+    projects::{mask_frame, parse_project_entries, project_details, project_image_path, project_links, PROJECT_ART_HEIGHT, PROJECT_SIDE_WIDTH, PROJECTS_DIR},
+    resume::RESUME_PATH,
+    themes::PaletteKey,
+};
+
+/// Something wrong with a content file, with the line it is on when known.
+struct Diagnostic {
+    path: String,
+    line: Option<usize>,
+    message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path, line, self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Characters in `text` that `font` has no glyph for, if there are any.
+fn missing_glyphs(text: &str, font: Option<&Font>) -> Option<String> {
+    let font = font?;
+    let mut missing: Vec<char> = text.chars()
+        .filter(|c| *c != '\n' && *c != '\r' && font.lookup_glyph_index(*c) == 0)
+        .collect();
+    missing.sort();
+    missing.dedup();
+    (!missing.is_empty()).then(|| missing.into_iter().collect())
+}
+
+/// Colour mask characters that leave the art under them in the default colours.
+fn is_blank_mask(c: char) -> bool {
+    c == ' ' || c == '.'
+}
+
+#[derive(Default)]
+struct Checker {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn report(&mut self, path: &str, line: Option<usize>, message: String) {
+        self.diagnostics.push(Diagnostic { path: path.to_string(), line, message });
+    }
+
+    fn read(&mut self, path: &str) -> Option<Vec<u8>> {
+        fs::read(path).map_err(|error| self.report(path, None, format!("could not be read: {}", error))).ok()
+    }
+
+    fn read_text(&mut self, path: &str) -> Option<String> {
+        let bytes = self.read(path)?;
+        String::from_utf8(bytes).map_err(|_| self.report(path, None, String::from("is not valid UTF-8"))).ok()
+    }
+
+    /// Checks a markdown file against what the renderer handles. Front matter
+    /// isn't rendered, so only the text after it is checked.
+    fn check_markdown(&mut self, path: &str, text: &str, font: Option<&Font>) {
+        if text.is_empty() {
+            self.report(path, None, String::from("is empty"));
+            return;
+        }
+
+        let (_, body) = split_front_matter(text);
+        let first_line = text[..text.len() - body.len()].lines().count();
+        for (line_index, line) in body.lines().enumerate() {
+            let line_number = Some(first_line + line_index + 1);
+            if let Some(missing) = missing_glyphs(line, font) {
+                self.report(path, line_number, format!("the font can't draw {:?}", missing));
+            }
+        }
+        for (line_number, construct) in unsupported_constructs(body) {
+            self.report(path, Some(first_line + line_number), String::from(construct));
+        }
+    }
+
+    /// Checks what the projects screen shows of a project. Problems are
+    /// reported against its markdown, which may be where they were written.
+    fn check_project(&mut self, path: &str, project: &ProjectInfo, terminal_font: Option<&Font>) {
+        let max_width = PROJECT_SIDE_WIDTH - 2;
+        let max_height = PROJECT_ART_HEIGHT - 2;
+        let art_lines = || project.ascii_art.iter().flatten();
+        if let Some(widest) = art_lines().map(|line| line.chars().count()).max().filter(|width| *width > max_width) {
+            self.report(path, None, format!("{}: ascii_art is {} columns wide, the art panel fits {}", project.name, widest, max_width));
+        }
+        if let Some(tallest) = project.ascii_art.iter().map(|frame| frame.len()).max().filter(|height| *height > max_height) {
+            self.report(path, None, format!("{}: ascii_art is {} lines tall, the art panel fits {}", project.name, tallest, max_height));
+        }
+
+        let frame_count = project.ascii_art.len();
+        if project.frame_delays.len() > frame_count {
+            self.report(path, None, format!("{}: frame_delays has {} delays for {} frames", project.name, project.frame_delays.len(), frame_count));
+        }
+        if project.frame_duration.iter().chain(&project.frame_delays).any(|seconds| *seconds <= 0f32) {
+            self.report(path, None, format!("{}: frames must be shown for more than 0 seconds", project.name));
+        }
+
+        for (mask_name, mask) in [("ascii_art_colors", &project.ascii_art_colors), ("ascii_art_backgrounds", &project.ascii_art_backgrounds)] {
+            if let Some(c) = mask.iter().flatten().flat_map(|line| line.chars()).find(|c| !is_blank_mask(*c) && PaletteKey::from_char(*c).is_none()) {
+                let keys: Vec<String> = PaletteKey::ALL.iter().map(|key| format!("{} ({:?})", key.char(), key)).collect();
+                self.report(path, None, format!("{}: {:?} in {} is not a palette key, use one of {}", project.name, c, mask_name, keys.join(", ")));
+            }
+            if mask.len() > 1 && mask.len() != frame_count {
+                self.report(path, None, format!("{}: {} has {} frames, it needs 1 or one for each of the {} frames of ascii_art", project.name, mask_name, mask.len(), frame_count));
+            }
+            let outside_art = (0..frame_count).any(|frame| mask_frame(mask, frame).iter().enumerate().any(|(row, mask_line)| {
+                let art_width = project.ascii_art[frame].get(row).map_or(0, |art_line| art_line.chars().count());
+                mask_line.chars().skip(art_width).any(|c| !is_blank_mask(c))
+            }));
+            if outside_art {
+                self.report(path, None, format!("{}: {} colours cells outside ascii_art", project.name, mask_name));
+            }
+        }
+
+        let shown_text = [project.name.clone(), project_details(project).unwrap_or_default()].into_iter().chain(art_lines().cloned());
+        if let Some(missing) = missing_glyphs(&shown_text.collect::<Vec<String>>().join(""), terminal_font) {
+            self.report(path, None, format!("{}: the font can't draw {:?}", project.name, missing));
+        }
+    }
+
+    /// Entries of the project list, or the markdown files under `projects/` when there is no list.
+    fn project_entries(&mut self, list_path: Option<&str>) -> Option<Vec<Value>> {
+        if let Some(list_path) = list_path.filter(|list_path| Path::new(list_path).exists()) {
+            let bytes = self.read(list_path)?;
+            return parse_project_entries(list_path, &bytes).map_err(|error| self.report(list_path, None, error.to_string())).ok();
+        }
+
+        let mut markdown_files: Vec<String> = fs::read_dir(PROJECTS_DIR).into_iter().flatten()
+            .filter_map(|dir_entry| dir_entry.ok()?.file_name().into_string().ok())
+            .filter(|file_name| file_name.ends_with(".md"))
+            .collect();
+        markdown_files.sort();
+        Some(markdown_files.into_iter().map(Value::String).collect())
+    }
+
+    fn check_projects(&mut self, list_path: Option<&str>, terminal_font: Option<&Font>, markdown_font: Option<&Font>) {
+        let Some(entries) = self.project_entries(list_path) else {
+            return;
+        };
+        if entries.is_empty() {
+            self.report(list_path.unwrap_or(PROJECTS_DIR), None, String::from("no projects to show"));
+        }
+
+        let mut page_urls: HashMap<String, String> = HashMap::new();
+        for entry in &entries {
+            let markdown = entry_markdown(entry).unwrap_or_default();
+            let path = format!("{}/{}", PROJECTS_DIR, markdown);
+            let text = self.read_text(&path).unwrap_or_default();
+
+            let project = match merge_project(entry, markdown, &text) {
+                Ok(project) => project,
+                Err(message) => {
+                    self.report(&path, None, message);
+                    continue;
+                }
+            };
+            self.check_project(&path, &project, terminal_font);
+            if !text.is_empty() || Path::new(&path).exists() {
+                self.check_markdown(&path, &text, markdown_font);
+            }
+            for image_path in image_paths(&text).into_iter().map(project_image_path) {
+                if let Some(bytes) = self.read(&image_path) {
+                    if let Err(message) = decode_image(&bytes) {
+                        self.report(&image_path, None, message);
+                    }
+                }
+            }
+
+            let page_url = project.url.as_ref().and_then(|_| project_links(&project).into_iter().next());
+            if let Some(page_url) = page_url {
+                match page_urls.get(&page_url.url) {
+                    Some(other) => self.report(&path, None, format!("{} and {} both link to {}", other, project.name, page_url.url)),
+                    None => {
+                        page_urls.insert(page_url.url, project.name.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    fn load_manifest(&mut self) -> Vec<AssetEntry> {
+        let Some(bytes) = self.read(MANIFEST_PATH) else {
+            return built_in_manifest();
+        };
+        parse_manifest(MANIFEST_PATH, &bytes).unwrap_or_else(|error| {
+            self.report(MANIFEST_PATH, None, error.to_string());
+            built_in_manifest()
+        })
+    }
+
+    fn load_fonts(&mut self, manifest: &[AssetEntry]) -> HashMap<AssetKey, Font> {
+        let mut fonts = HashMap::new();
+        for entry in manifest.iter().filter(|entry| entry.kind() == AssetKind::Font) {
+            let Some(bytes) = self.read(&entry.path) else {
+                continue;
+            };
+            match Font::from_bytes(bytes, FontSettings::default()) {
+                Ok(font) => {
+                    fonts.insert(entry.key, font);
+                }
+                Err(error) => self.report(&entry.path, None, format!("is not a usable font: {}", error)),
+            }
+        }
+        fonts
+    }
+}
+
+/// Checks the content in the current directory the way the site will load it,
+/// printing every problem found. Fails when there are any, so it can gate deploys.
+pub fn run_check() -> ExitCode {
+    let mut checker = Checker::default();
+    let manifest = checker.load_manifest();
+    let fonts = checker.load_fonts(&manifest);
+    // Project names and art are drawn in the terminal font, markdown in the resume font.
+    let terminal_font = fonts.get(&AssetKey::TerminalFont);
+    let markdown_font = fonts.get(&AssetKey::ResumeFont).or(terminal_font);
+
+    let list_path = manifest.iter().find(|entry| entry.kind() == AssetKind::ProjectList).map(|entry| entry.path.as_str());
+    checker.check_projects(list_path, terminal_font, markdown_font);
+    if let Some(text) = checker.read_text(RESUME_PATH) {
+        checker.check_markdown(RESUME_PATH, &text, markdown_font);
+    }
+
+    for diagnostic in &checker.diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    match checker.diagnostics.len() {
+        0 => {
+            println!("Content OK");
+            ExitCode::SUCCESS
+        }
+        count => {
+            eprintln!("{} problem{} found", count, if count == 1 { "" } else { "s" });
+            ExitCode::FAILURE
+        }
+    }
+}
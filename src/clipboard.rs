@@ -0,0 +1,18 @@
+#[cfg(not(target_arch="wasm32"))]
+pub fn copy_to_clipboard(text: &str) {
+    macroquad::miniquad::window::clipboard_set(text);
+}
+
+#[cfg(target_arch="wasm32")]
+extern "C" {
+    fn write_clipboard(ptr: *const u8, len: u32);
+}
+
+/// Hands the UTF-8 bytes straight to the page, which reads exactly `len` of
+/// them, so text with a NUL in it is copied whole.
+#[cfg(target_arch="wasm32")]
+pub fn copy_to_clipboard(text: &str) {
+    unsafe {
+        write_clipboard(text.as_ptr(), text.len() as u32);
+    }
+}
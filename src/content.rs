@@ -0,0 +1,103 @@
+use std::{io, sync::OnceLock};
+
+use macroquad::{miniquad::fs, prelude::*};
+
+use crate::bundle::Bundle;
+
+pub const BUNDLE_PATH: &str = "content.bundle";
+
+static BUNDLE: OnceLock<Bundle> = OnceLock::new();
+
+/// Content built into the binary, generated by `build.rs`.
+#[cfg(feature = "embed-content")]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded_content.rs"));
+}
+
+#[cfg(feature = "embed-content")]
+fn embedded_file(path: &str) -> Option<Vec<u8>> {
+    embedded::EMBEDDED_FILES.iter()
+        .find(|(embedded_path, _)| *embedded_path == path)
+        .map(|(_, bytes)| bytes.to_vec())
+}
+#[cfg(not(feature = "embed-content"))]
+fn embedded_file(_path: &str) -> Option<Vec<u8>> {
+    None
+}
+
+/// Whether the content bundle is fetched before anything else. Content built
+/// into the binary has no use for one.
+pub fn wants_bundle() -> bool {
+    !cfg!(feature = "embed-content")
+}
+
+/// Serves later loads from the fetched content bundle. Without one, files are
+/// loaded one by one from the content directory, which is what development
+/// builds do.
+pub fn use_bundle(bytes: Vec<u8>) -> Result<(), String> {
+    let _ = BUNDLE.set(Bundle::parse(bytes)?);
+    Ok(())
+}
+
+/// `path` from the content built into the binary or the bundle, or `None`
+/// if it has to be loaded from the content directory.
+pub fn preloaded_file(path: &str) -> Option<Result<Vec<u8>, macroquad::Error>> {
+    if let Some(bytes) = embedded_file(path) {
+        return Some(Ok(bytes));
+    }
+
+    let result = BUNDLE.get()?.read(path)?;
+    Some(result.map_err(|message| macroquad::Error::FileError {
+        kind: fs::Error::IOError(io::Error::new(io::ErrorKind::InvalidData, message)),
+        path: path.to_string(),
+    }))
+}
+
+/// Whether files are served from memory rather than the content directory,
+/// in which case editing the directory changes nothing.
+pub fn is_preloaded() -> bool {
+    cfg!(feature = "embed-content") || BUNDLE.get().is_some()
+}
+
+/// Paths of the files in the content directory `dir`, like `projects`.
+/// Browsers can't list a directory, so without a bundle there is nothing to
+/// find there.
+pub fn list_files(dir: &str) -> Vec<String> {
+    let prefix = format!("{}/", dir);
+    let mut paths: Vec<String> = match BUNDLE.get() {
+        Some(bundle) => bundle.paths().filter(|path| path.starts_with(&prefix)).map(String::from).collect(),
+        None => embedded_paths().unwrap_or_else(|| list_dir(dir)).into_iter().filter(|path| path.starts_with(&prefix)).collect(),
+    };
+    paths.sort();
+    paths
+}
+
+#[cfg(feature = "embed-content")]
+fn embedded_paths() -> Option<Vec<String>> {
+    Some(embedded::EMBEDDED_FILES.iter().map(|(path, _)| path.to_string()).collect())
+}
+#[cfg(not(feature = "embed-content"))]
+fn embedded_paths() -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn list_dir(dir: &str) -> Vec<String> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    read_dir.filter_map(|dir_entry| Some(format!("{}/{}", dir, dir_entry.ok()?.file_name().to_str()?))).collect()
+}
+#[cfg(target_arch = "wasm32")]
+fn list_dir(_dir: &str) -> Vec<String> {
+    Vec::new()
+}
+
+/// Same as `macroquad::file::load_file`, but served from the content built
+/// into the binary or the bundle when they have the file.
+pub async fn load_file(path: &str) -> Result<Vec<u8>, macroquad::Error> {
+    match preloaded_file(path) {
+        Some(result) => result,
+        None => macroquad::file::load_file(path).await,
+    }
+}
@@ -0,0 +1,39 @@
+// Which files make up the site's content. Shared by the `pack_bundle` binary,
+// the build script that embeds content into native builds and the native
+// content watcher.
+
+use std::{fs, path::{Path, PathBuf}};
+
+/// Files the site loads from the content directory, skipped when missing.
+pub const CONTENT_FILES: &[&str] = &["assets.json", "projects.json", "resume.md", "bindings.json"];
+/// Directories whose every file the site may load.
+pub const CONTENT_DIRS: &[&str] = &["fonts", "projects"];
+
+/// Every content file under `content_dir`, as the path the site loads it by.
+pub fn collect_paths(content_dir: &Path) -> Result<Vec<String>, String> {
+    let mut paths: Vec<String> = CONTENT_FILES.iter()
+        .filter(|path| content_dir.join(path).is_file())
+        .map(|path| path.to_string())
+        .collect();
+
+    let mut dirs: Vec<PathBuf> = CONTENT_DIRS.iter().map(PathBuf::from).collect();
+    while let Some(dir) = dirs.pop() {
+        let Ok(read_dir) = fs::read_dir(content_dir.join(&dir)) else {
+            continue;
+        };
+        for dir_entry in read_dir {
+            let dir_entry = dir_entry.map_err(|error| format!("Could not read {}: {}", dir.display(), error))?;
+            let relative = dir.join(dir_entry.file_name());
+            if dir_entry.path().is_dir() {
+                dirs.push(relative);
+            } else {
+                // Load paths always use forward slashes.
+                let parts: Vec<String> = relative.components().map(|part| part.as_os_str().to_string_lossy().into_owned()).collect();
+                paths.push(parts.join("/"));
+            }
+        }
+    }
+
+    paths.sort();
+    Ok(paths)
+}
@@ -0,0 +1,50 @@
+use std::{fmt, string::FromUtf8Error};
+
+/// Something that went wrong loading one of the site's files.
+#[derive(Debug)]
+pub enum Error {
+    /// The file could not be fetched.
+    Load { path: String, source: macroquad::Error },
+    /// The file is not valid UTF-8.
+    Decode { path: String, source: FromUtf8Error },
+    /// The file is not JSON of the expected shape.
+    Parse { path: String, source: serde_json::Error },
+    /// The file is not a font that can be drawn with.
+    Font { path: String, source: macroquad::Error },
+    /// The file parsed but its contents don't make sense.
+    Invalid { path: String, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// The file that failed.
+    pub fn path(&self) -> &str {
+        match self {
+            Error::Load { path, .. } | Error::Decode { path, .. } | Error::Parse { path, .. } | Error::Font { path, .. } | Error::Invalid { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Load { path, .. } => write!(f, "Could not load {}", path),
+            Error::Decode { path, .. } => write!(f, "{} is not valid UTF-8", path),
+            Error::Parse { path, source } => write!(f, "Could not parse {}: {}", path, source),
+            Error::Font { path, .. } => write!(f, "{} is not a usable font", path),
+            Error::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Load { source, .. } | Error::Font { source, .. } => Some(source),
+            Error::Decode { source, .. } => Some(source),
+            Error::Parse { source, .. } => Some(source),
+            Error::Invalid { .. } => None,
+        }
+    }
+}
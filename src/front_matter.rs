@@ -0,0 +1,274 @@
+use serde_json::{Map, Value};
+
+use crate::models::ProjectInfo;
+
+const DELIMITER: &str = "---";
+/// Indent that `|` block lines start with, which is not part of the text.
+const BLOCK_INDENT: &str = "  ";
+/// Line between two frames of art.
+const FRAME_SEPARATOR: &str = "~~~";
+/// Fields holding art, which may be split into frames.
+const ART_KEYS: [&str; 3] = ["ascii_art", "ascii_art_colors", "ascii_art_backgrounds"];
+
+/// Splits a `---` delimited header off the top of `text`, returning it and the
+/// rest. Text without a header is all body.
+pub fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let Some(after_open) = text.strip_prefix(DELIMITER).and_then(|rest| rest.strip_prefix("\r\n").or(rest.strip_prefix('\n'))) else {
+        return (None, text);
+    };
+
+    let mut position = 0;
+    for line in after_open.split_inclusive('\n') {
+        if line.trim_end() == DELIMITER {
+            return (Some(&after_open[..position]), &after_open[position + line.len()..]);
+        }
+        position += line.len();
+    }
+    (None, text)
+}
+
+/// Quotes are stripped, anything else is kept as written.
+fn scalar(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|rest| rest.strip_suffix(quote)) {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// Parses the small part of YAML front matter uses: `key: value` lines whose
+/// value is a plain or quoted string, a `[a, b]` list, `- item` lines below
+/// the key, `- name: url` lines for links, or `|` followed by lines kept as
+/// written after a two space indent.
+pub fn parse_front_matter(header: &str) -> Result<Map<String, Value>, String> {
+    let lines: Vec<&str> = header.lines().collect();
+    let mut fields = Map::new();
+    let mut line_index = 0;
+
+    while line_index < lines.len() {
+        let line = lines[line_index];
+        let line_number = line_index + 1;
+        line_index += 1;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            return Err(format!("line {}: unexpected indent", line_number));
+        }
+
+        let (key, value) = line.split_once(':').ok_or(format!("line {}: expected `key: value`", line_number))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        let parsed = if value == "|" {
+            let mut block = Vec::new();
+            while let Some(block_line) = lines.get(line_index).filter(|block_line| block_line.starts_with(BLOCK_INDENT) || block_line.trim().is_empty()) {
+                block.push(Value::String(block_line.get(BLOCK_INDENT.len()..).unwrap_or("").to_string()));
+                line_index += 1;
+            }
+            while block.last().is_some_and(|last| last.as_str() == Some("")) {
+                block.pop();
+            }
+            Value::Array(block)
+        }
+        else if value.is_empty() {
+            let mut items = Vec::new();
+            while let Some(item) = lines.get(line_index).and_then(|item_line| item_line.trim_start().strip_prefix("- ")) {
+                items.push(match item.split_once(": ") {
+                    Some((name, url)) => Value::Object(Map::from_iter([
+                        (String::from("name"), Value::String(scalar(name))),
+                        (String::from("url"), Value::String(scalar(url))),
+                    ])),
+                    None => Value::String(scalar(item)),
+                });
+                line_index += 1;
+            }
+            Value::Array(items)
+        }
+        else if let Some(list) = value.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            Value::Array(list.split(',').map(scalar).filter(|item| !item.is_empty()).map(Value::String).collect())
+        }
+        else {
+            Value::String(scalar(value))
+        };
+
+        if fields.insert(key.to_string(), parsed).is_some() {
+            return Err(format!("line {}: `{}` is set twice", line_number, key));
+        }
+    }
+
+    Ok(fields)
+}
+
+fn seconds(text: &str) -> Result<Value, String> {
+    let seconds: f64 = text.parse().map_err(|_| format!("`{}` is not a number of seconds", text))?;
+    Ok(Value::from(seconds))
+}
+
+/// Splits the lines of art at `~~~` lines into frames, leaving out blank
+/// lines at the end of each.
+fn split_frames(lines: &[Value]) -> Value {
+    Value::Array(lines.split(|line| line.as_str() == Some(FRAME_SEPARATOR)).map(|frame| {
+        let end = frame.iter().rposition(|line| line.as_str() != Some("")).map_or(0, |last| last + 1);
+        Value::Array(frame[..end].to_vec())
+    }).collect())
+}
+
+/// Builds a project from its entry in `projects.json`, which is either the
+/// markdown file name or a project object, and the markdown itself. Front
+/// matter fills in and overrides the entry, so a file can describe itself.
+pub fn merge_project(entry: &Value, markdown: &str, text: &str) -> Result<ProjectInfo, String> {
+    let mut fields = match entry {
+        Value::Object(fields) => fields.clone(),
+        _ => Map::new(),
+    };
+
+    if let (Some(header), _) = split_front_matter(text) {
+        let mut front_matter = parse_front_matter(header)?;
+        // Everything in front matter is text, but some fields are numbers.
+        if let Some(Value::String(order)) = front_matter.get("order") {
+            let order: i64 = order.parse().map_err(|_| format!("order `{}` is not a whole number", order))?;
+            front_matter.insert(String::from("order"), Value::from(order));
+        }
+        if let Some(Value::String(duration)) = front_matter.get("frame_duration") {
+            let duration = seconds(duration)?;
+            front_matter.insert(String::from("frame_duration"), duration);
+        }
+        if let Some(Value::Array(delays)) = front_matter.get("frame_delays") {
+            let delays = delays.iter().map(|delay| seconds(delay.as_str().unwrap_or_default())).collect::<Result<Vec<Value>, String>>()?;
+            front_matter.insert(String::from("frame_delays"), Value::Array(delays));
+        }
+        for key in ART_KEYS {
+            if let Some(Value::Array(lines)) = front_matter.get(key) {
+                let frames = split_frames(lines);
+                front_matter.insert(key.to_string(), frames);
+            }
+        }
+        fields.extend(front_matter);
+    }
+
+    fields.insert(String::from("markdown"), Value::String(markdown.to_string()));
+    if !fields.contains_key("name") {
+        let file_name = markdown.rsplit('/').next().unwrap_or(markdown);
+        let name = file_name.strip_suffix(".md").unwrap_or(file_name);
+        fields.insert(String::from("name"), Value::String(name.to_string()));
+    }
+
+    let project: ProjectInfo = serde_json::from_value(Value::Object(fields)).map_err(|error| error.to_string())?;
+    // The project list has nothing to show for a blank name.
+    if project.name.trim().is_empty() {
+        return Err(String::from("name is empty, leave it out to use the file name"));
+    }
+    Ok(project)
+}
+
+/// The markdown file a `projects.json` entry points at.
+pub fn entry_markdown(entry: &Value) -> Option<&str> {
+    match entry {
+        Value::String(markdown) => Some(markdown),
+        Value::Object(fields) => fields.get("markdown")?.as_str(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_header_off_the_body() {
+        assert_eq!(split_front_matter("---\nname: Pong\n---\n# Pong\n"), (Some("name: Pong\n"), "# Pong\n"));
+        assert_eq!(split_front_matter("---\r\nname: Pong\r\n---\r\nbody"), (Some("name: Pong\r\n"), "body"));
+        assert_eq!(split_front_matter("---\n---\n"), (Some(""), ""));
+    }
+
+    #[test]
+    fn text_without_a_closed_header_is_all_body() {
+        assert_eq!(split_front_matter("# Pong\n---\n"), (None, "# Pong\n---\n"));
+        assert_eq!(split_front_matter("---\nname: Pong\n# Pong\n"), (None, "---\nname: Pong\n# Pong\n"));
+        assert_eq!(split_front_matter("----\nname: Pong\n---\n"), (None, "----\nname: Pong\n---\n"));
+    }
+
+    #[test]
+    fn parses_scalars() {
+        let fields = parse_front_matter("name: \"Pong: the game\"\n# a comment\n\norder: 3\nurl: 'pong'\n").unwrap();
+        assert_eq!(fields["name"], "Pong: the game");
+        assert_eq!(fields["order"], "3");
+        assert_eq!(fields["url"], "pong");
+        assert_eq!(fields.len(), 3);
+    }
+
+    #[test]
+    fn parses_block_scalars() {
+        let fields = parse_front_matter("ascii_art: |\n  +--+\n\n  |  |\n    indented\n\nname: Pong\n").unwrap();
+        assert_eq!(fields["ascii_art"], serde_json::json!(["+--+", "", "|  |", "  indented"]));
+        assert_eq!(fields["name"], "Pong");
+    }
+
+    #[test]
+    fn parses_lists() {
+        let fields = parse_front_matter("tags: [games, \"rust\", ]\nlanguages:\n  - Rust\n  - 'C#'\nempty: []\n").unwrap();
+        assert_eq!(fields["tags"], serde_json::json!(["games", "rust"]));
+        assert_eq!(fields["languages"], serde_json::json!(["Rust", "C#"]));
+        assert_eq!(fields["empty"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn parses_named_links() {
+        let fields = parse_front_matter("links:\n  - source: https://github.com/x/pong\n  - demo: 'https://pong.example'\n").unwrap();
+        assert_eq!(fields["links"], serde_json::json!([
+            { "name": "source", "url": "https://github.com/x/pong" },
+            { "name": "demo", "url": "https://pong.example" },
+        ]));
+    }
+
+    #[test]
+    fn rejects_broken_lines() {
+        assert_eq!(parse_front_matter("name: Pong\nname: Ping\n"), Err(String::from("line 2: `name` is set twice")));
+        assert_eq!(parse_front_matter("name: Pong\n  order: 2\n"), Err(String::from("line 2: unexpected indent")));
+        assert_eq!(parse_front_matter("just text\n"), Err(String::from("line 1: expected `key: value`")));
+    }
+
+    #[test]
+    fn front_matter_overrides_the_entry() {
+        let entry = serde_json::json!({ "markdown": "pong.md", "name": "Old name", "tags": ["old"], "order": 1 });
+        let project = merge_project(&entry, "pong.md", "---\nname: Pong\norder: 2\n---\n# Pong\n").unwrap();
+        assert_eq!(project.name, "Pong");
+        assert_eq!(project.order, Some(2));
+        assert_eq!(project.tags, ["old"]);
+        assert_eq!(project.markdown, "pong.md");
+    }
+
+    #[test]
+    fn name_defaults_to_the_file_stem() {
+        let entry = Value::String(String::from("games/pong.md"));
+        assert_eq!(merge_project(&entry, "games/pong.md", "# Pong\n").unwrap().name, "pong");
+        assert_eq!(merge_project(&entry, "notes", "").unwrap().name, "notes");
+    }
+
+    #[test]
+    fn rejects_empty_names() {
+        let entry = Value::String(String::from("pong.md"));
+        assert!(merge_project(&entry, "pong.md", "---\nname: \"\"\n---\n").is_err());
+        assert!(merge_project(&entry, "pong.md", "---\nname: '  '\n---\n").is_err());
+        let named_entry = serde_json::json!({"markdown": "pong.md", "name": ""});
+        assert!(merge_project(&named_entry, "pong.md", "").is_err());
+    }
+
+    #[test]
+    fn splits_art_into_frames() {
+        let text = "---\nascii_art: |\n  o\n\n  ~~~\n   o\n  ~~~\n    o\nascii_art_colors: |\n  a\nframe_delays: [0.5, 1]\n---\n";
+        let project = merge_project(&Value::String(String::from("ball.md")), "ball.md", text).unwrap();
+        assert_eq!(project.ascii_art, [vec!["o"], vec![" o"], vec!["  o"]]);
+        assert_eq!(project.ascii_art_colors, [vec!["a"]]);
+        assert_eq!(project.frame_delays, [0.5, 1.0]);
+    }
+
+    #[test]
+    fn rejects_fields_that_should_be_numbers() {
+        let entry = Value::String(String::from("pong.md"));
+        assert!(merge_project(&entry, "pong.md", "---\norder: first\n---\n").is_err());
+        assert!(merge_project(&entry, "pong.md", "---\nframe_duration: slow\n---\n").is_err());
+    }
+}
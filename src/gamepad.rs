@@ -0,0 +1,65 @@
+/// Buttons of the standard gamepad layout, numbered like the browser Gamepad API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GamepadButton {
+    South = 0,
+    East = 1,
+    West = 2,
+    North = 3,
+    LeftShoulder = 4,
+    RightShoulder = 5,
+    LeftTrigger = 6,
+    RightTrigger = 7,
+    Select = 8,
+    Start = 9,
+    LeftStick = 10,
+    RightStick = 11,
+    DPadUp = 12,
+    DPadDown = 13,
+    DPadLeft = 14,
+    DPadRight = 15,
+}
+
+impl GamepadButton {
+    fn mask(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Buttons held on any connected gamepad this frame and the one before.
+#[derive(Default)]
+pub struct GamepadState {
+    down: u32,
+    previous: u32,
+}
+
+impl GamepadState {
+    pub fn update(&mut self) {
+        self.previous = self.down;
+        self.down = read_buttons();
+    }
+
+    pub fn is_down(&self, button: GamepadButton) -> bool {
+        self.down & button.mask() != 0
+    }
+
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.is_down(button) && self.previous & button.mask() == 0
+    }
+}
+
+/// Macroquad has no gamepad support on desktop, so only the browser build
+/// reads pads for now.
+#[cfg(not(target_arch="wasm32"))]
+fn read_buttons() -> u32 {
+    0
+}
+
+#[cfg(target_arch="wasm32")]
+extern "C" {
+    fn gamepad_buttons() -> u32;
+}
+
+#[cfg(target_arch="wasm32")]
+fn read_buttons() -> u32 {
+    unsafe { gamepad_buttons() }
+}
@@ -0,0 +1,104 @@
+use macroquad::miniquad::window::{dpi_scale, screen_size};
+
+use crate::models::TerminalState;
+
+/// Grid size the layouts are designed around. The font is scaled so that at
+/// least this many cells fit, unless that would make it unreadably small.
+pub const PREFERRED_TERM_WIDTH: usize = 80;
+pub const PREFERRED_TERM_HEIGHT: usize = 20;
+
+/// Smallest font size in logical pixels before the grid starts losing columns
+/// and rows instead of shrinking the text further.
+pub const MIN_FONT_SIZE: f32 = 14.0;
+
+/// Range the reader can pinch the font size to, relative to the fitted size.
+pub const MIN_FONT_SCALE: f32 = 0.5;
+pub const MAX_FONT_SCALE: f32 = 3.0;
+
+/// Cells are half as wide as they are tall.
+pub const CELL_ASPECT: f32 = 0.5;
+
+/// Share of the window the grid may cover, the rest is left for the CRT vignette.
+const GRID_FILL: f32 = 0.8;
+
+const MIN_TERM_WIDTH: usize = 24;
+const MIN_TERM_HEIGHT: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridSize {
+    pub width: usize,
+    pub height: usize,
+    pub font_size: f32,
+}
+
+/// Largest font size, in physical pixels, that fits `cols` x `rows` cells in the window.
+pub fn fit_font_size(cols: usize, rows: usize, screen_w: f32, screen_h: f32) -> f32 {
+    f32::min(
+        screen_h * GRID_FILL / rows as f32,
+        screen_w * GRID_FILL / (cols as f32 * CELL_ASPECT),
+    )
+}
+
+/// Grid for the window with the fitted font multiplied by `font_scale`, never
+/// going below the minimum font size.
+pub fn compute_grid_size(screen_w: f32, screen_h: f32, dpi: f32, font_scale: f32) -> GridSize {
+    let font_size = fit_font_size(PREFERRED_TERM_WIDTH, PREFERRED_TERM_HEIGHT, screen_w, screen_h)
+        .max(MIN_FONT_SIZE * dpi) * font_scale;
+    let font_size = font_size.max(MIN_FONT_SIZE * dpi);
+
+    let width = (screen_w * GRID_FILL / (font_size * CELL_ASPECT)) as usize;
+    let height = (screen_h * GRID_FILL / font_size) as usize;
+
+    GridSize {
+        width: width.max(MIN_TERM_WIDTH),
+        height: height.max(MIN_TERM_HEIGHT),
+        font_size,
+    }
+}
+
+/// Recomputes the grid from the current window and font scale. Returns true when the number of
+/// columns or rows changed and the current screen needs to be laid out again.
+pub fn update_grid_size(terminal_state: &mut TerminalState) -> bool {
+    let (screen_w, screen_h) = screen_size();
+    let grid = compute_grid_size(screen_w, screen_h, dpi_scale(), terminal_state.font_scale);
+
+    terminal_state.font_size = grid.font_size;
+    terminal_state.terminal_width_px = screen_w;
+    terminal_state.terminal_height_px = screen_h;
+
+    let changed = grid.width != terminal_state.term_width || grid.height != terminal_state.term_height;
+    terminal_state.term_width = grid.width;
+    terminal_state.term_height = grid.height;
+
+    changed
+}
+
+/// Space in physical pixels between the window edge and the first column and
+/// row of the current buffer. Drawing and hit-testing both go through this so
+/// they always agree on where a cell is.
+pub fn grid_padding(terminal_state: &TerminalState) -> (f32, f32) {
+    let term_col_count = terminal_state.cell_buffer.first().map_or(0, |line| line.len()) as f32;
+    let term_row_count = terminal_state.cell_buffer.len() as f32;
+
+    let horizontal_padding = (terminal_state.terminal_width_px - term_col_count * terminal_state.font_size * CELL_ASPECT) / 2f32;
+    let vertical_padding = (terminal_state.terminal_height_px - term_row_count * terminal_state.font_size) / 2f32;
+    (horizontal_padding, vertical_padding)
+}
+
+/// Returns the buffer cell under a point given in physical pixels, if any.
+/// Rows span from a fifth of a cell below one baseline to the same point below
+/// the next, matching the background rectangles in `draw_terminal_cells`.
+pub fn cell_at_position(terminal_state: &TerminalState, x: f32, y: f32) -> Option<(usize, usize)> {
+    let (horizontal_padding, vertical_padding) = grid_padding(terminal_state);
+    let font_size = terminal_state.font_size;
+
+    let col = ((x - horizontal_padding) / (font_size * CELL_ASPECT)).floor();
+    let row = ((y - vertical_padding - font_size / 5f32) / font_size).floor() + 1f32;
+    if col < 0f32 || row < 0f32 {
+        return None;
+    }
+
+    let (col, row) = (col as usize, row as usize);
+    let line = terminal_state.cell_buffer.get(row)?;
+    (col < line.len()).then_some((col, row))
+}
@@ -0,0 +1,33 @@
+use crate::{
+    input::{BindingScope, Bindings},
+    models::{BorderStyle, CellPanel, TerminalState},
+};
+
+/// Bindings for the current screen, one action per line.
+fn help_lines(bindings: &Bindings, scope: BindingScope) -> Vec<String> {
+    let actions = bindings.actions(scope);
+    let label_width = actions.iter().map(|(action, _)| action.label().len()).max().unwrap_or(0);
+
+    actions.iter().map(|(action, inputs)| {
+        let inputs: Vec<String> = inputs.iter().map(|input| input.to_string()).collect();
+        format!("{:<width$}  {}", action.label(), inputs.join(", "), width = label_width)
+    }).collect()
+}
+
+/// Panel listing the bindings of the current screen, centered on the buffer.
+pub fn help_panel(terminal_state: &TerminalState, bindings: &Bindings) -> CellPanel {
+    let lines = help_lines(bindings, BindingScope::of(terminal_state));
+    let buffer_height = terminal_state.cell_buffer.len();
+    let buffer_width = terminal_state.cell_buffer.first().map_or(0, |line| line.len());
+
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0).min(buffer_width.saturating_sub(4));
+    let height = lines.len().min(buffer_height.saturating_sub(4));
+    let offset_x = (buffer_width - width) / 2;
+    let offset_y = (buffer_height - height) / 2;
+
+    let mut panel = CellPanel::from_strings(&lines, width, height, offset_x, offset_y);
+    panel.title = Some(String::from("help"));
+    panel.focused = true;
+    panel.border_style = terminal_state.border_style.overlay(BorderStyle::Double);
+    panel
+}
@@ -0,0 +1,165 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use macroquad::prelude::*;
+
+use crate::{
+    assets::{apply_asset, AssetEntry, AssetKind},
+    content,
+    error::{Error, Result},
+    models::{TermSubState, TerminalState},
+    projects::{load_projects, setup_projects, update_project_buffer, PROJECTS_DIR},
+    resume::{layout_resume, RESUME_PATH},
+    setup_main_state,
+    utils::load_text,
+};
+
+/// Seconds between checks of the content directory.
+const POLL_INTERVAL: f32 = 0.5;
+
+/// Watches the content directory while authoring on native builds, so edits
+/// show up without restarting. Modification times are polled, which is
+/// plenty for a handful of files.
+pub struct ContentWatcher {
+    enabled: bool,
+    modified: HashMap<String, SystemTime>,
+    since_poll: f32,
+}
+
+/// When each content file was last modified.
+#[cfg(not(target_arch = "wasm32"))]
+fn modified_times() -> HashMap<String, SystemTime> {
+    let paths = crate::content_files::collect_paths(std::path::Path::new(".")).unwrap_or_default();
+    paths.into_iter()
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+/// The browser can't see the server's files.
+#[cfg(target_arch = "wasm32")]
+fn modified_times() -> HashMap<String, SystemTime> {
+    HashMap::new()
+}
+
+impl ContentWatcher {
+    /// Starts watching, unless content is served from memory and edits wouldn't show anyway.
+    pub fn new() -> Self {
+        let enabled = !cfg!(target_arch = "wasm32") && !content::is_preloaded();
+        ContentWatcher {
+            enabled,
+            modified: match enabled {
+                true => modified_times(),
+                false => HashMap::new(),
+            },
+            since_poll: 0f32,
+        }
+    }
+
+    /// Content files created or modified since the last poll.
+    pub fn changed_files(&mut self, dt: f32) -> Vec<String> {
+        self.since_poll += dt;
+        if !self.enabled || self.since_poll < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.since_poll = 0f32;
+
+        let modified = modified_times();
+        let mut changed: Vec<String> = modified.iter()
+            .filter(|(path, time)| self.modified.get(*path) != Some(*time))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.sort();
+        self.modified = modified;
+        changed
+    }
+}
+
+/// Loads changed content files again and redraws the current screen with
+/// them, keeping its scroll position. Files that fail to load or parse are
+/// reported in a toast and the old contents stay up.
+pub async fn reload_content(terminal_state: &mut TerminalState, manifest: &[AssetEntry], changed: &[String]) {
+    for path in changed {
+        info!("{} changed, reloading", path);
+        terminal_state.skipped_files.remove(path);
+        if let Err(error) = reload_file(terminal_state, manifest, path).await {
+            warn!("{}", error);
+            terminal_state.overlay.toast(&error.to_string());
+        }
+    }
+}
+
+async fn reload_file(terminal_state: &mut TerminalState, manifest: &[AssetEntry], path: &str) -> Result<()> {
+    // Taken before the project list is replaced, which moves projects around.
+    let selected_markdown = selected_project_markdown(terminal_state);
+
+    if path == RESUME_PATH {
+        terminal_state.resume_markdown = Some(load_text(RESUME_PATH).await?);
+        layout_resume(terminal_state);
+        return Ok(());
+    }
+
+    if let Some(entry) = manifest.iter().find(|entry| entry.path == path && entry.kind() == AssetKind::ProjectList) {
+        let bytes = content::load_file(path).await.map_err(|source| Error::Load { path: path.to_string(), source })?;
+        apply_asset(terminal_state, entry, &bytes)?;
+        return reload_projects(terminal_state, selected_markdown.as_deref()).await;
+    }
+
+    if path.starts_with(&format!("{}/", PROJECTS_DIR)) {
+        // Its front matter may have changed the project list too.
+        terminal_state.project_files.forget(path);
+        terminal_state.project_images.remove(path);
+        return reload_projects(terminal_state, selected_markdown.as_deref()).await;
+    }
+    Ok(())
+}
+
+/// The markdown file of the project selected on the projects screen.
+fn selected_project_markdown(terminal_state: &TerminalState) -> Option<String> {
+    match terminal_state.sub_state {
+        TermSubState::Projects { selected_project_index, .. } => terminal_state.projects.get(selected_project_index).map(|project| project.markdown.clone()),
+        _ => None,
+    }
+}
+
+/// Builds the project list again and the projects screen with it.
+async fn reload_projects(terminal_state: &mut TerminalState, selected_markdown: Option<&str>) -> Result<()> {
+    let mut errors = load_projects(terminal_state).await.into_iter();
+    let result = reload_projects_screen(terminal_state, selected_markdown);
+    let first_error = errors.next();
+    for error in errors {
+        warn!("{}", error);
+        terminal_state.overlay.toast(&error.to_string());
+    }
+    match first_error {
+        Some(error) => Err(error),
+        None => result,
+    }
+}
+
+/// Builds the projects screen again from a new project list, staying on the
+/// project with `selected_markdown` and its scroll position if it is still
+/// there. Otherwise the selection stays at the same place in the list.
+fn reload_projects_screen(terminal_state: &mut TerminalState, selected_markdown: Option<&str>) -> Result<()> {
+    let TermSubState::Projects { selected_project_index, project_about_scroll, main_focus, .. } = terminal_state.sub_state else {
+        return Ok(());
+    };
+    if terminal_state.projects.is_empty() {
+        setup_main_state(terminal_state);
+        terminal_state.overlay.toast("No projects to show");
+        return Ok(());
+    }
+
+    // The selected project is restored and redrawn below, which reports its own load errors.
+    setup_projects(terminal_state).ok();
+    let project_count = terminal_state.projects.len();
+    let same_project = selected_markdown.and_then(|markdown| terminal_state.projects.iter().position(|project| project.markdown == markdown));
+    if let TermSubState::Projects { selected_project_index: ref mut selected, project_about_scroll: ref mut scroll, main_focus: ref mut focus, .. } = terminal_state.sub_state {
+        (*selected, *scroll) = match same_project {
+            Some(index) => (index, project_about_scroll),
+            None => (selected_project_index.min(project_count - 1), 0),
+        };
+        *focus = main_focus;
+    }
+    update_project_buffer(terminal_state)
+}
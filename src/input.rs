@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    content,
+    gamepad::{GamepadButton, GamepadState},
+    models::{TermSubState, TerminalState},
+};
+
+/// Everything the terminal can be asked to do, whatever key or button asked for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    FocusLeft,
+    FocusRight,
+    Select,
+    Back,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Search,
+    /// Deletes the last typed character while text input is active.
+    Erase,
+    Help,
+    Palette,
+    OpenLinks,
+}
+
+impl Action {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+            Action::FocusLeft => "Focus left",
+            Action::FocusRight => "Focus right",
+            Action::Select => "Select",
+            Action::Back => "Back",
+            Action::PageUp => "Page up",
+            Action::PageDown => "Page down",
+            Action::Home => "Go to top",
+            Action::End => "Go to bottom",
+            Action::Search => "Search",
+            Action::Erase => "Erase",
+            Action::Help => "Toggle help",
+            Action::Palette => "Command palette",
+            Action::OpenLinks => "Open project links",
+        }
+    }
+}
+
+/// Set of bindings in effect. Each screen has its own so a key can mean
+/// different things in different places, and typing into a text field does
+/// not also trigger the letter bindings used for navigation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum BindingScope {
+    Main,
+    Projects,
+    Resume,
+    TextInput,
+    Dialog,
+}
+
+impl BindingScope {
+    pub fn of(terminal_state: &TerminalState) -> BindingScope {
+        if terminal_state.overlay.active_dialog().is_some() {
+            return BindingScope::Dialog;
+        }
+        if terminal_state.palette.is_some() {
+            return BindingScope::TextInput;
+        }
+
+        match terminal_state.sub_state {
+            TermSubState::Projects { search: Some(_), .. } => BindingScope::TextInput,
+            TermSubState::Projects { .. } => BindingScope::Projects,
+            TermSubState::Resume(_) => BindingScope::Resume,
+            _ => BindingScope::Main,
+        }
+    }
+}
+
+/// A key with the modifiers that have to be held with it, or a gamepad button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    Key { key: KeyCode, ctrl: bool, shift: bool },
+    Pad(GamepadButton),
+}
+
+const KEY_NAMES: [(&str, KeyCode); 56] = [
+    ("Up", KeyCode::Up), ("Down", KeyCode::Down), ("Left", KeyCode::Left), ("Right", KeyCode::Right),
+    ("Enter", KeyCode::Enter), ("Backspace", KeyCode::Backspace), ("Escape", KeyCode::Escape),
+    ("Space", KeyCode::Space), ("Tab", KeyCode::Tab), ("Slash", KeyCode::Slash),
+    ("PageUp", KeyCode::PageUp), ("PageDown", KeyCode::PageDown), ("Home", KeyCode::Home), ("End", KeyCode::End),
+    ("F1", KeyCode::F1), ("F2", KeyCode::F2), ("F3", KeyCode::F3), ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5), ("F6", KeyCode::F6),
+    ("A", KeyCode::A), ("B", KeyCode::B), ("C", KeyCode::C), ("D", KeyCode::D), ("E", KeyCode::E),
+    ("F", KeyCode::F), ("G", KeyCode::G), ("H", KeyCode::H), ("I", KeyCode::I), ("J", KeyCode::J),
+    ("K", KeyCode::K), ("L", KeyCode::L), ("M", KeyCode::M), ("N", KeyCode::N), ("O", KeyCode::O),
+    ("P", KeyCode::P), ("Q", KeyCode::Q), ("R", KeyCode::R), ("S", KeyCode::S), ("T", KeyCode::T),
+    ("U", KeyCode::U), ("V", KeyCode::V), ("W", KeyCode::W), ("X", KeyCode::X), ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("0", KeyCode::Key0), ("1", KeyCode::Key1), ("2", KeyCode::Key2), ("3", KeyCode::Key3), ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5), ("6", KeyCode::Key6), ("7", KeyCode::Key7), ("8", KeyCode::Key8), ("9", KeyCode::Key9),
+];
+
+const PAD_NAMES: [(&str, GamepadButton); 16] = [
+    ("South", GamepadButton::South), ("East", GamepadButton::East),
+    ("West", GamepadButton::West), ("North", GamepadButton::North),
+    ("LeftShoulder", GamepadButton::LeftShoulder), ("RightShoulder", GamepadButton::RightShoulder),
+    ("LeftTrigger", GamepadButton::LeftTrigger), ("RightTrigger", GamepadButton::RightTrigger),
+    ("Select", GamepadButton::Select), ("Start", GamepadButton::Start),
+    ("LeftStick", GamepadButton::LeftStick), ("RightStick", GamepadButton::RightStick),
+    ("DPadUp", GamepadButton::DPadUp), ("DPadDown", GamepadButton::DPadDown),
+    ("DPadLeft", GamepadButton::DPadLeft), ("DPadRight", GamepadButton::DPadRight),
+];
+
+impl Input {
+    /// Parses names like `W`, `Shift+Slash`, `Ctrl+K` or `Pad:South`.
+    pub fn parse(name: &str) -> Option<Input> {
+        if let Some(button) = name.strip_prefix("Pad:") {
+            return PAD_NAMES.iter().find(|(pad_name, _)| *pad_name == button).map(|(_, button)| Input::Pad(*button));
+        }
+
+        let mut ctrl = false;
+        let mut shift = false;
+        let mut key_name = name;
+        loop {
+            if let Some(rest) = key_name.strip_prefix("Ctrl+") {
+                ctrl = true;
+                key_name = rest;
+            }
+            else if let Some(rest) = key_name.strip_prefix("Shift+") {
+                shift = true;
+                key_name = rest;
+            }
+            else {
+                break;
+            }
+        }
+
+        KEY_NAMES.iter().find(|(known_name, _)| *known_name == key_name).map(|(_, key)| Input::Key { key: *key, ctrl, shift })
+    }
+
+    fn is_pressed(&self, gamepad: &GamepadState) -> bool {
+        match *self {
+            Input::Key { key, ctrl, shift } => is_key_pressed(key) && ctrl == is_ctrl_down() && shift == is_shift_down(),
+            Input::Pad(button) => gamepad.is_pressed(button),
+        }
+    }
+
+    fn is_down(&self, gamepad: &GamepadState) -> bool {
+        match *self {
+            Input::Key { key, ctrl, shift } => is_key_down(key) && ctrl == is_ctrl_down() && shift == is_shift_down(),
+            Input::Pad(button) => gamepad.is_down(button),
+        }
+    }
+}
+
+impl std::fmt::Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Input::Key { key, ctrl, shift } => {
+                let key_name = KEY_NAMES.iter().find(|(_, known_key)| *known_key == key).map_or("?", |(name, _)| name);
+                if ctrl {
+                    write!(f, "Ctrl+")?;
+                }
+                if shift {
+                    write!(f, "Shift+")?;
+                }
+                write!(f, "{}", key_name)
+            }
+            Input::Pad(button) => {
+                let pad_name = PAD_NAMES.iter().find(|(_, known_button)| *known_button == button).map_or("?", |(name, _)| name);
+                write!(f, "Pad:{}", pad_name)
+            }
+        }
+    }
+}
+
+/// Command on macOS is treated the same as Control.
+pub fn is_ctrl_down() -> bool {
+    is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)
+        || is_key_down(KeyCode::LeftSuper) || is_key_down(KeyCode::RightSuper)
+}
+
+pub fn is_shift_down() -> bool {
+    is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift)
+}
+
+const NAVIGATION_BINDINGS: [(Action, &[&str]); 14] = [
+    (Action::Up, &["Up", "W", "Pad:DPadUp"]),
+    (Action::Down, &["Down", "S", "Pad:DPadDown"]),
+    (Action::FocusLeft, &["Left", "A", "Pad:DPadLeft", "Pad:LeftShoulder"]),
+    (Action::FocusRight, &["Right", "D", "Pad:DPadRight", "Pad:RightShoulder"]),
+    (Action::Select, &["Enter", "Pad:South"]),
+    (Action::Back, &["Backspace", "Escape", "Pad:East"]),
+    (Action::PageUp, &["PageUp", "Pad:LeftTrigger"]),
+    (Action::PageDown, &["PageDown", "Pad:RightTrigger"]),
+    (Action::Home, &["Home"]),
+    (Action::End, &["End"]),
+    (Action::Search, &["Slash", "Pad:North"]),
+    (Action::Help, &["F1", "Shift+Slash", "Pad:Start"]),
+    (Action::Palette, &["Ctrl+K", "Pad:Select"]),
+    (Action::OpenLinks, &["O", "Pad:West"]),
+];
+
+const TEXT_INPUT_BINDINGS: [(Action, &[&str]); 7] = [
+    (Action::Up, &["Up", "Pad:DPadUp"]),
+    (Action::Down, &["Down", "Pad:DPadDown"]),
+    (Action::Select, &["Enter", "Pad:South"]),
+    (Action::Back, &["Escape", "Pad:East"]),
+    (Action::Erase, &["Backspace", "Pad:West"]),
+    (Action::Help, &["F1"]),
+    (Action::Palette, &["Ctrl+K"]),
+];
+
+/// Actions that keep firing while their key or button is held.
+const REPEATING_ACTIONS: [Action; 5] = [Action::Up, Action::Down, Action::PageUp, Action::PageDown, Action::Erase];
+
+/// Seconds a key has to be held before it starts repeating, and repeats per second after that.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct KeyRepeat {
+    pub delay: f32,
+    pub rate: f32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        KeyRepeat { delay: 0.4, rate: 15.0 }
+    }
+}
+
+/// Shape of `bindings.json`: optional `key_repeat` and `smooth_scrolling`
+/// settings, then for each scope the actions to rebind and the full list of
+/// inputs that should trigger them, for example
+/// `{ "key_repeat": { "delay": 0.3, "rate": 20 }, "Resume": { "Down": ["Down", "J"], "Up": ["Up", "K"] } }`.
+/// Actions that are left out keep their default inputs.
+#[derive(Deserialize)]
+struct BindingsConfig {
+    key_repeat: Option<KeyRepeat>,
+    smooth_scrolling: Option<bool>,
+    #[serde(flatten)]
+    scopes: HashMap<BindingScope, HashMap<Action, Vec<String>>>,
+}
+
+pub struct Bindings {
+    scopes: HashMap<BindingScope, Vec<(Action, Vec<Input>)>>,
+    pub key_repeat: KeyRepeat,
+    /// Animate scrolled panels between line offsets instead of snapping.
+    pub smooth_scrolling: bool,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let table = |bindings: &[(Action, &[&str])], actions: &[Action]| -> Vec<(Action, Vec<Input>)> {
+            bindings.iter()
+                .filter(|(action, _)| actions.contains(action))
+                .map(|(action, names)| (*action, names.iter().filter_map(|name| Input::parse(name)).collect()))
+                .collect()
+        };
+
+        let main_actions = [Action::Up, Action::Down, Action::Select, Action::Home, Action::End, Action::Help, Action::Palette];
+        let resume_actions = [Action::Up, Action::Down, Action::Back, Action::PageUp, Action::PageDown, Action::Home, Action::End, Action::Help, Action::Palette];
+        let projects_actions = NAVIGATION_BINDINGS.map(|(action, _)| action);
+        let text_input_actions = TEXT_INPUT_BINDINGS.map(|(action, _)| action);
+        let dialog_actions = [Action::Up, Action::Down, Action::FocusLeft, Action::FocusRight, Action::Select, Action::Back];
+
+        let scopes = HashMap::from([
+            (BindingScope::Main, table(&NAVIGATION_BINDINGS, &main_actions)),
+            (BindingScope::Projects, table(&NAVIGATION_BINDINGS, &projects_actions)),
+            (BindingScope::Resume, table(&NAVIGATION_BINDINGS, &resume_actions)),
+            (BindingScope::TextInput, table(&TEXT_INPUT_BINDINGS, &text_input_actions)),
+            (BindingScope::Dialog, table(&NAVIGATION_BINDINGS, &dialog_actions)),
+        ]);
+
+        Bindings { scopes, key_repeat: KeyRepeat::default(), smooth_scrolling: true }
+    }
+}
+
+impl Bindings {
+    /// Actions bound in `scope` with their inputs, in the order they are listed in help.
+    pub fn actions(&self, scope: BindingScope) -> &[(Action, Vec<Input>)] {
+        self.scopes.get(&scope).map_or(&[], |actions| actions.as_slice())
+    }
+
+    fn apply_config(&mut self, config: BindingsConfig) -> Result<(), String> {
+        self.key_repeat = config.key_repeat.unwrap_or(self.key_repeat);
+        self.smooth_scrolling = config.smooth_scrolling.unwrap_or(self.smooth_scrolling);
+
+        for (scope, actions) in config.scopes {
+            let scope_actions = self.scopes.entry(scope).or_default();
+            for (action, names) in actions {
+                let inputs = names.iter()
+                    .map(|name| Input::parse(name).ok_or_else(|| format!("Unknown input \"{}\" for {:?} in {:?}", name, action, scope)))
+                    .collect::<Result<Vec<Input>, String>>()?;
+
+                match scope_actions.iter_mut().find(|(bound_action, _)| *bound_action == action) {
+                    Some((_, bound_inputs)) => *bound_inputs = inputs,
+                    None => scope_actions.push((action, inputs)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Default bindings with the overrides from `bindings.json`, if there is one.
+/// A broken file is reported and ignored rather than leaving the site without controls.
+pub async fn load_bindings() -> Bindings {
+    let mut bindings = Bindings::default();
+
+    let Ok(bytes) = content::load_file("bindings.json").await else {
+        info!("No bindings.json, using the default bindings");
+        return bindings;
+    };
+
+    let result = serde_json::from_slice::<BindingsConfig>(&bytes)
+        .map_err(|error| error.to_string())
+        .and_then(|config| bindings.apply_config(config));
+
+    if let Err(error) = result {
+        warn!("Ignoring bindings.json: {}", error);
+        return Bindings::default();
+    }
+
+    bindings
+}
+
+/// Actions triggered this frame along with any text typed, resolved through
+/// the bindings for the current screen.
+#[derive(Default)]
+pub struct InputContext {
+    pub bindings: Bindings,
+    pub gamepad: GamepadState,
+    pressed: Vec<Action>,
+    /// Repeating action being held, how long for and how many times it has repeated.
+    held: Option<(Action, f32, u32)>,
+    /// Characters typed this frame, only collected while a text field has focus.
+    pub typed: Vec<char>,
+}
+
+impl InputContext {
+    pub fn update(&mut self, terminal_state: &TerminalState) {
+        self.gamepad.update();
+
+        let scope = BindingScope::of(terminal_state);
+        self.pressed = self.bindings.actions(scope).iter()
+            .filter(|(_, inputs)| inputs.iter().any(|input| input.is_pressed(&self.gamepad)))
+            .map(|(action, _)| *action)
+            .collect();
+        self.repeat_held_action(scope);
+
+        // Always drain the queue so keys pressed while navigating are not typed
+        // into the next text field that opens.
+        let typed = std::iter::from_fn(get_char_pressed).filter(|c| !c.is_control());
+        self.typed = match scope {
+            BindingScope::TextInput => typed.collect(),
+            _ => {
+                typed.for_each(drop);
+                Vec::new()
+            }
+        };
+    }
+
+    /// Fires the held action again once it has been down for the repeat delay,
+    /// then at the repeat rate for as long as it stays down.
+    fn repeat_held_action(&mut self, scope: BindingScope) {
+        let held_action = self.bindings.actions(scope).iter()
+            .filter(|(action, _)| REPEATING_ACTIONS.contains(action))
+            .find(|(_, inputs)| inputs.iter().any(|input| input.is_down(&self.gamepad)))
+            .map(|(action, _)| *action);
+
+        let (action, held_for, repeats) = match (held_action, self.held) {
+            (Some(action), Some((previous, held_for, repeats))) if action == previous => (action, held_for + get_frame_time(), repeats),
+            (Some(action), _) => (action, 0f32, 0),
+            (None, _) => {
+                self.held = None;
+                return;
+            }
+        };
+
+        let KeyRepeat { delay, rate } = self.bindings.key_repeat;
+        let due_repeats = match held_for >= delay {
+            true => ((held_for - delay) * rate) as u32 + 1,
+            false => 0,
+        };
+        if due_repeats > repeats && !self.pressed.contains(&action) {
+            self.pressed.push(action);
+        }
+        self.held = Some((action, held_for, due_repeats.max(repeats)));
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn any_pressed(&self) -> bool {
+        !self.pressed.is_empty() || !self.typed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: KeyCode, ctrl: bool, shift: bool) -> Input {
+        Input::Key { key, ctrl, shift }
+    }
+
+    fn config(json: &str) -> BindingsConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn inputs(bindings: &Bindings, scope: BindingScope, action: Action) -> Vec<Input> {
+        bindings.actions(scope).iter().find(|(bound_action, _)| *bound_action == action).map(|(_, inputs)| inputs.clone()).unwrap_or_default()
+    }
+
+    #[test]
+    fn parses_keys_with_modifiers_and_pad_buttons() {
+        assert_eq!(Input::parse("W"), Some(key(KeyCode::W, false, false)));
+        assert_eq!(Input::parse("Shift+Slash"), Some(key(KeyCode::Slash, false, true)));
+        assert_eq!(Input::parse("Ctrl+K"), Some(key(KeyCode::K, true, false)));
+        assert_eq!(Input::parse("Ctrl+Shift+1"), Some(key(KeyCode::Key1, true, true)));
+        assert_eq!(Input::parse("Pad:South"), Some(Input::Pad(GamepadButton::South)));
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert_eq!(Input::parse("Pad:Nope"), None);
+        assert_eq!(Input::parse("Nope"), None);
+        assert_eq!(Input::parse("Ctrl+"), None);
+        assert_eq!(Input::parse("South"), None);
+    }
+
+    #[test]
+    fn names_round_trip_through_display() {
+        for name in ["Shift+Slash", "Ctrl+K", "Ctrl+Shift+1", "Pad:South", "PageDown"] {
+            assert_eq!(Input::parse(name).unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn an_override_replaces_only_the_actions_it_names_in_its_scope() {
+        let mut bindings = Bindings::default();
+        bindings.apply_config(config(r#"{ "Resume": { "Down": ["Down", "J"] } }"#)).unwrap();
+
+        assert_eq!(inputs(&bindings, BindingScope::Resume, Action::Down), [key(KeyCode::Down, false, false), key(KeyCode::J, false, false)]);
+        let defaults = Bindings::default();
+        assert_eq!(inputs(&bindings, BindingScope::Resume, Action::Up), inputs(&defaults, BindingScope::Resume, Action::Up));
+        assert_eq!(inputs(&bindings, BindingScope::Projects, Action::Down), inputs(&defaults, BindingScope::Projects, Action::Down));
+        assert_eq!(bindings.actions(BindingScope::Resume).len(), defaults.actions(BindingScope::Resume).len());
+    }
+
+    #[test]
+    fn an_override_can_bind_an_action_new_to_its_scope() {
+        let mut bindings = Bindings::default();
+        bindings.apply_config(config(r#"{ "Main": { "Search": ["Slash"] } }"#)).unwrap();
+        assert_eq!(inputs(&bindings, BindingScope::Main, Action::Search), [key(KeyCode::Slash, false, false)]);
+    }
+
+    #[test]
+    fn settings_are_read_alongside_the_scopes() {
+        let mut bindings = Bindings::default();
+        bindings.apply_config(config(r#"{ "key_repeat": { "delay": 0.25, "rate": 20 }, "smooth_scrolling": false }"#)).unwrap();
+        assert_eq!((bindings.key_repeat.delay, bindings.key_repeat.rate), (0.25, 20.0));
+        assert!(!bindings.smooth_scrolling);
+    }
+
+    #[test]
+    fn unknown_names_in_the_config_are_errors() {
+        let error = Bindings::default().apply_config(config(r#"{ "Resume": { "Down": ["Pad:Nope"] } }"#)).unwrap_err();
+        assert!(error.contains("Pad:Nope"), "{}", error);
+        assert!(serde_json::from_str::<BindingsConfig>(r#"{ "Resme": { "Down": ["J"] } }"#).is_err());
+        assert!(serde_json::from_str::<BindingsConfig>(r#"{ "Resume": { "Dwon": ["J"] } }"#).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use crate::models::CellPanel;
+
+/// Size of a node along its parent's split direction. Sizes count the node's
+/// border lines, and neighbouring nodes share the border between them.
+#[derive(Clone, Copy, Debug)]
+pub enum Constraint {
+    Fixed(usize),
+    Percentage(usize),
+    /// Splits whatever the other constraints leave over evenly.
+    Fill,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug)]
+pub enum Layout {
+    /// A bordered panel, identified by its index in the panel list.
+    Panel(usize),
+    Split {
+        direction: Direction,
+        children: Vec<(Constraint, Layout)>,
+    },
+}
+
+/// Inner area of a bordered panel, in the same terms as the `CellPanel` fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PanelRect {
+    pub offset_x: usize,
+    pub offset_y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl PanelRect {
+    pub fn apply(&self, panel: &mut CellPanel) {
+        panel.offset_x = self.offset_x;
+        panel.offset_y = self.offset_y;
+        panel.width = self.width;
+        panel.height = self.height;
+    }
+}
+
+impl Layout {
+    pub fn horizontal(children: Vec<(Constraint, Layout)>) -> Self {
+        Layout::Split { direction: Direction::Horizontal, children }
+    }
+
+    pub fn vertical(children: Vec<(Constraint, Layout)>) -> Self {
+        Layout::Split { direction: Direction::Vertical, children }
+    }
+
+    /// Computes the inner rect of every panel, indexed by panel id. Ids that do
+    /// not appear in the tree get an empty rect.
+    pub fn compute(&self, term_width: usize, term_height: usize) -> Vec<PanelRect> {
+        let mut rects = Vec::new();
+        self.compute_node(0, 0, term_width, term_height, &mut rects);
+        rects
+    }
+
+    /// Moves and resizes `panels` to match the layout.
+    pub fn apply(&self, panels: &mut [CellPanel], term_width: usize, term_height: usize) {
+        for (panel, rect) in panels.iter_mut().zip(self.compute(term_width, term_height)) {
+            rect.apply(panel);
+        }
+    }
+
+    fn compute_node(&self, x: usize, y: usize, width: usize, height: usize, rects: &mut Vec<PanelRect>) {
+        match self {
+            Layout::Panel(id) => {
+                if rects.len() <= *id {
+                    rects.resize(*id + 1, PanelRect::default());
+                }
+                rects[*id] = PanelRect {
+                    offset_x: x + 1,
+                    offset_y: y + 1,
+                    width: width.saturating_sub(2),
+                    height: height.saturating_sub(2),
+                };
+            }
+            Layout::Split { direction, children } => {
+                let length = match direction {
+                    Direction::Horizontal => width,
+                    Direction::Vertical => height,
+                };
+                let sizes = resolve_sizes(children.iter().map(|(constraint, _)| *constraint), length);
+
+                let mut position = 0;
+                for ((_, child), size) in children.iter().zip(sizes) {
+                    match direction {
+                        Direction::Horizontal => child.compute_node(x + position, y, size, height, rects),
+                        Direction::Vertical => child.compute_node(x, y + position, width, size, rects),
+                    }
+                    position += size.saturating_sub(1);
+                }
+            }
+        }
+    }
+}
+
+/// Splits `length` cells between the constraints. Neighbours overlap by the one
+/// border cell they share, so the sizes add up to `length + children - 1`.
+fn resolve_sizes(constraints: impl Iterator<Item = Constraint>, length: usize) -> Vec<usize> {
+    let constraints: Vec<Constraint> = constraints.collect();
+    if constraints.is_empty() {
+        return Vec::new();
+    }
+
+    let available = length + constraints.len() - 1;
+    let mut sizes: Vec<usize> = constraints.iter().map(|constraint| match constraint {
+        Constraint::Fixed(size) => *size,
+        Constraint::Percentage(percent) => available * percent / 100,
+        Constraint::Fill => 0,
+    }).collect();
+
+    let used: usize = sizes.iter().sum();
+    let remaining = available.saturating_sub(used);
+    let fill_count = constraints.iter().filter(|constraint| matches!(constraint, Constraint::Fill)).count();
+
+    if let Some(share) = remaining.checked_div(fill_count) {
+        let mut fills_seen = 0;
+        for (size, constraint) in sizes.iter_mut().zip(constraints.iter()) {
+            if let Constraint::Fill = constraint {
+                fills_seen += 1;
+                *size = share;
+                if fills_seen == fill_count {
+                    *size += remaining % fill_count;
+                }
+            }
+        }
+    }
+    else if let Some(last) = sizes.last_mut() {
+        *last += remaining;
+    }
+
+    // Shrink from the end when the fixed sizes ask for more than there is.
+    let mut overflow = sizes.iter().sum::<usize>().saturating_sub(available);
+    for size in sizes.iter_mut().rev() {
+        let taken = usize::min(overflow, *size);
+        *size -= taken;
+        overflow -= taken;
+    }
+
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_and_fill_share_the_border() {
+        assert_eq!(resolve_sizes([Constraint::Fixed(21), Constraint::Fill].into_iter(), 80), vec![21, 60]);
+    }
+
+    #[test]
+    fn percentage_counts_the_shared_borders() {
+        assert_eq!(resolve_sizes([Constraint::Percentage(25), Constraint::Fill].into_iter(), 80), vec![20, 61]);
+        assert_eq!(resolve_sizes([Constraint::Fixed(10), Constraint::Percentage(50), Constraint::Fill].into_iter(), 98), vec![10, 50, 40]);
+    }
+
+    #[test]
+    fn fill_remainder_goes_to_the_last_fill() {
+        assert_eq!(resolve_sizes([Constraint::Fill, Constraint::Fill, Constraint::Fill].into_iter(), 11), vec![4, 4, 5]);
+        assert_eq!(resolve_sizes([Constraint::Fill, Constraint::Fixed(3), Constraint::Fill].into_iter(), 10), vec![4, 3, 5]);
+    }
+
+    #[test]
+    fn percentage_remainder_goes_to_the_last_child_without_fills() {
+        assert_eq!(resolve_sizes([Constraint::Percentage(33), Constraint::Percentage(33), Constraint::Percentage(33)].into_iter(), 10), vec![3, 3, 6]);
+    }
+
+    #[test]
+    fn oversized_constraints_shrink_from_the_end() {
+        assert_eq!(resolve_sizes([Constraint::Fixed(30), Constraint::Fixed(30)].into_iter(), 40), vec![30, 11]);
+        assert_eq!(resolve_sizes([Constraint::Fixed(30), Constraint::Fill, Constraint::Fixed(30)].into_iter(), 20), vec![22, 0, 0]);
+    }
+
+    #[test]
+    fn no_children_gets_no_sizes() {
+        assert!(resolve_sizes(std::iter::empty(), 80).is_empty());
+    }
+
+    #[test]
+    fn neighbouring_panels_share_one_border_line() {
+        let layout = Layout::horizontal(vec![
+            (Constraint::Fixed(21), Layout::Panel(0)),
+            (Constraint::Fill, Layout::vertical(vec![
+                (Constraint::Fixed(8), Layout::Panel(1)),
+                (Constraint::Fill, Layout::Panel(2)),
+            ])),
+        ]);
+        let rects = layout.compute(80, 20);
+
+        assert_eq!(rects[0], PanelRect { offset_x: 1, offset_y: 1, width: 19, height: 18 });
+        assert_eq!(rects[1], PanelRect { offset_x: 21, offset_y: 1, width: 58, height: 6 });
+        assert_eq!(rects[2], PanelRect { offset_x: 21, offset_y: 8, width: 58, height: 11 });
+    }
+
+    #[test]
+    fn panels_missing_from_the_tree_are_empty() {
+        let rects = Layout::vertical(vec![(Constraint::Fill, Layout::Panel(2))]).compute(10, 5);
+
+        assert_eq!(rects.len(), 3);
+        assert_eq!(rects[0], PanelRect::default());
+        assert_eq!(rects[2], PanelRect { offset_x: 1, offset_y: 1, width: 8, height: 3 });
+    }
+}
@@ -1,18 +1,52 @@
+use crate::assets::{apply_asset, load_manifest, AssetEntry};
+use crate::content::{self, BUNDLE_PATH};
 use crate::draw_terminal_cells;
+use crate::error::{Error, Result};
+use crate::grid::{fit_font_size, update_grid_size, MIN_FONT_SIZE};
+use crate::input::{load_bindings, Action, InputContext};
 use crate::models::{
     Cell,
     FontType, ProjectInfo
 };
-use crate::terminal_templates::BALLOON_SPINNER;
+use crate::mouse::{self, HitTarget, MouseContext};
+use crate::overlay::{self, DialogKind, RETRY_BUTTON};
+use crate::projects::{entry_markdown_path, project_from_markdown, project_list_entries, project_without_markdown, set_projects};
+use crate::terminal_templates::LOAD_TEMPLATE_COMPACT;
 use crate::utils::generate_cell_line;
 use crate::{models::{TermSubState, TerminalState}, terminal_templates::{BALLOON_SPINNER_CHARS, LOAD_TEMPLATE}};
+use macroquad::miniquad::window::dpi_scale;
 use macroquad::experimental::coroutines::{start_coroutine, Coroutine};
 use macroquad::miniquad::window::screen_size;
 use macroquad::prelude::*;
+use serde_json::Value;
 
-fn setup_loading_state(terminal_state: &mut TerminalState, first_file: &str) -> (usize, usize) {
+/// Rows between the banner and the file list: a blank line and the progress bar.
+const STATUS_ROWS: usize = 2;
+const ANIM_TIME_STEP : f32 = 0.1f32;
+
+/// Shrinks the grid font so the whole loading banner stays on screen.
+fn fit_loading_font(terminal_state: &mut TerminalState) {
+    let (screen_w, screen_h) = screen_size();
+    let cols = terminal_state.cell_buffer.iter().map(|line| line.len()).max().unwrap_or(1);
+    let rows = terminal_state.cell_buffer.len();
+    terminal_state.font_size = f32::min(terminal_state.font_size, fit_font_size(cols, rows, screen_w, screen_h));
+}
+
+/// Draws the banner with room for the progress bar and one line per file
+/// under it. Returns the number of rows the banner takes.
+fn setup_loading_state(terminal_state: &mut TerminalState, file_count: usize) -> usize {
     terminal_state.sub_state = TermSubState::Load;
-    terminal_state.cell_buffer = LOAD_TEMPLATE.iter().map(|line| {
+
+    let (screen_w, screen_h) = screen_size();
+    let template_width = LOAD_TEMPLATE[0].chars().count();
+    let status_rows = STATUS_ROWS + file_count;
+    let template: &[&str] = if fit_font_size(template_width, LOAD_TEMPLATE.len() + status_rows, screen_w, screen_h) < MIN_FONT_SIZE * dpi_scale() {
+        &LOAD_TEMPLATE_COMPACT
+    } else {
+        &LOAD_TEMPLATE
+    };
+
+    terminal_state.cell_buffer = template.iter().map(|line| {
         line.chars().map(|c| {
             Cell {
                 char: c,
@@ -23,51 +57,191 @@ fn setup_loading_state(terminal_state: &mut TerminalState, first_file: &str) ->
         }).collect()
 
     }).collect();
-    let rect_length = terminal_state.cell_buffer[0].len();
-    let loading_str_len = 9;
-    let padding = " ".repeat(( rect_length - loading_str_len )/ 2);
-    let loading_str : Vec<Cell> = generate_cell_line(&format!("{}{} Loading", padding, BALLOON_SPINNER[0]));
-    let first_file_padding = " ".repeat(( rect_length - first_file.len() ) / 2);
-    terminal_state.cell_buffer.push(loading_str);
-
-    let first_file_str = generate_cell_line(&format!("{}{}", first_file_padding, first_file));
-    terminal_state.cell_buffer.push(first_file_str);
-
-    let cell_x = ( rect_length - loading_str_len )/ 2;
-    let cell_y = terminal_state.cell_buffer.len() - 2;
-    (cell_x, cell_y)
+    let width = terminal_state.cell_buffer[0].len();
+    terminal_state.cell_buffer.extend(vec![vec![Cell::default(); width]; status_rows]);
+    fit_loading_font(terminal_state);
+    template.len()
 }
 
-pub fn start_file_coroutine(file_path: &str) -> Coroutine<Result<Vec<u8>, macroquad::Error>> {
+/// Columns and extra rows the loading banner is padded with while an error dialog is shown over it.
+const ERROR_DIALOG_WIDTH: usize = 48;
+const ERROR_DIALOG_ROWS: usize = 8;
+
+/// Pads the loading banner so an error dialog fits over it.
+fn make_room_for_dialog(terminal_state: &mut TerminalState) {
+    let width = terminal_state.cell_buffer.iter().map(|line| line.len()).max().unwrap_or(0).max(ERROR_DIALOG_WIDTH);
+    for line in terminal_state.cell_buffer.iter_mut() {
+        line.resize(width, Cell::default());
+    }
+    terminal_state.cell_buffer.extend(vec![vec![Cell::default(); width]; ERROR_DIALOG_ROWS]);
+    fit_loading_font(terminal_state);
+}
+
+pub fn start_file_coroutine(file_path: &str) -> Coroutine<std::result::Result<Vec<u8>, macroquad::Error>> {
     let file_path = file_path.to_string();
-    return start_coroutine(async move {
-        return macroquad::file::load_file(&file_path).await;
-    });
+    start_coroutine(async move {
+        content::load_file(&file_path).await
+    })
 }
 
-pub async fn run_loading_screen(terminal_state: &mut TerminalState, material: &Material) {
-    const FILES_TO_LOAD : [&'static str; 6]= [
-        "fonts/TerminalFont.ttf", 
-        "fonts/UbuntuMonoNerdFontMono-Bold.ttf", 
-        "fonts/UbuntuMonoNerdFontMono-BoldItalic.ttf",
-        "fonts/UbuntuMonoNerdFontMono-Italic.ttf",
-        "fonts/UbuntuMonoNerdFontMono-Regular.ttf",
-        "projects.json"
-    ];
-    let animated_cell_pos = setup_loading_state(terminal_state, FILES_TO_LOAD[0]);
-    const ANIM_TIME_STEP : f32 = 0.1f32;
-    let mut current_frame_time = 0f32;
+/// Size of the file before it is loaded, when that can be known up front.
+#[cfg(not(target_arch = "wasm32"))]
+fn expected_size(file_path: &str) -> Option<usize> {
+    std::fs::metadata(file_path).ok().map(|metadata| metadata.len() as usize)
+}
+/// Browsers only report the size once the download is done.
+#[cfg(target_arch = "wasm32")]
+fn expected_size(_file_path: &str) -> Option<usize> {
+    None
+}
+
+enum FileStatus {
+    Loading(Coroutine<std::result::Result<Vec<u8>, macroquad::Error>>),
+    /// Loaded, with its size in bytes.
+    Loaded(usize),
+    Failed(String),
+    Skipped,
+}
+
+/// What a file on the loading screen is put to once it arrives.
+enum LoadTarget {
+    /// The content bundle, which the files after it are served from.
+    Bundle,
+    Asset(AssetEntry),
+    /// A project's markdown, whose front matter is read into the project
+    /// built from its project list entry.
+    Project { entry: Value, project: Option<Box<ProjectInfo>> },
+}
+
+/// What the loading screen is waiting for. Each step starts once every file
+/// of the one before has settled, since its files depend on them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LoadStep {
+    Bundle,
+    Assets,
+    Projects,
+}
+
+struct LoadingFile {
+    path: String,
+    /// Asks to be retried or skipped when it fails, instead of being left out.
+    required: bool,
+    target: LoadTarget,
+    expected_size: Option<usize>,
+    status: FileStatus,
+}
+
+impl LoadingFile {
+    fn start(path: &str, required: bool, target: LoadTarget) -> Self {
+        LoadingFile {
+            path: path.to_string(),
+            required,
+            target,
+            expected_size: expected_size(path),
+            status: FileStatus::Loading(start_file_coroutine(path)),
+        }
+    }
+
+    /// Loaded or given up on. Optional files are given up on as soon as they fail.
+    fn is_settled(&self) -> bool {
+        match self.status {
+            FileStatus::Loaded(_) | FileStatus::Skipped => true,
+            FileStatus::Failed(_) => !self.required,
+            FileStatus::Loading(_) => false,
+        }
+    }
+
+    /// Puts the file's contents where they are used.
+    fn apply(&mut self, terminal_state: &mut TerminalState, bytes: Vec<u8>) -> Result<()> {
+        match &mut self.target {
+            LoadTarget::Bundle => content::use_bundle(bytes).map_err(|message| Error::Invalid { path: self.path.clone(), message }),
+            LoadTarget::Asset(entry) => apply_asset(terminal_state, entry, &bytes),
+            LoadTarget::Project { entry, project } => {
+                *project = Some(Box::new(project_from_markdown(terminal_state, entry, bytes)?));
+                Ok(())
+            }
+        }
+    }
+}
+
+fn kibibytes(bytes: usize) -> String {
+    format!("{:.1} KiB", bytes as f32 / 1024f32)
+}
+
+/// The progress bar and one status line per file, fitted to `width` columns.
+fn status_lines(files: &[LoadingFile], width: usize, spinner: char) -> Vec<String> {
+    let loaded_bytes: usize = files.iter().map(|file| match file.status {
+        FileStatus::Loaded(size) => size,
+        _ => 0,
+    }).sum();
+    let total_bytes: Option<usize> = files.iter().map(|file| match file.status {
+        FileStatus::Loaded(size) => Some(size),
+        _ if file.is_settled() => Some(0),
+        _ => file.expected_size,
+    }).sum();
+    let settled = files.iter().filter(|file| file.is_settled()).count();
+
+    // Without every size known up front, progress goes by the number of files instead.
+    let (progress, amount) = match total_bytes {
+        Some(total_bytes) => (loaded_bytes as f32 / total_bytes.max(1) as f32, format!("{} / {}", kibibytes(loaded_bytes), kibibytes(total_bytes))),
+        None => (settled as f32 / files.len() as f32, kibibytes(loaded_bytes)),
+    };
+    let amount = format!(" {}/{} files, {}", settled, files.len(), amount);
+    let bar_width = width.saturating_sub(amount.chars().count() + 2).max(4);
+    let filled = (progress * bar_width as f32).round() as usize;
+    let bar = format!("[{}{}]{}", "█".repeat(filled), "░".repeat(bar_width - filled.min(bar_width)), amount);
+
+    std::iter::once(bar).chain(files.iter().map(|file| match &file.status {
+        FileStatus::Loading(_) => format!("[ {}  ] {}", spinner, file.path),
+        FileStatus::Loaded(size) => format!("[ OK ] {} ({})", file.path, kibibytes(*size)),
+        FileStatus::Failed(message) => format!("[FAIL] {}", message),
+        FileStatus::Skipped => format!("[SKIP] {}", file.path),
+    }))
+    .map(|line| line.chars().take(width).collect())
+    .collect()
+}
 
-    let mut file_loading_index = 0;
+/// Rewrites the status rows under the banner.
+fn write_status_lines(terminal_state: &mut TerminalState, banner_height: usize, files: &[LoadingFile], spinner: char) {
+    let width = terminal_state.cell_buffer[0].len();
+    let lines = status_lines(files, width, spinner);
+    for (line_index, line) in lines.iter().enumerate() {
+        let mut cells = generate_cell_line(line);
+        cells.resize(width, Cell::default());
+        if let Some(buffer_line) = terminal_state.cell_buffer.get_mut(banner_height + STATUS_ROWS - 1 + line_index) {
+            *buffer_line = cells;
+        }
+    }
+}
+
+/// Fetches the content bundle, then loads every asset in the manifest at
+/// once, then every project's markdown, showing how far each file got. A file
+/// that fails is marked on the list. Required ones also bring up a dialog to
+/// retry them or skip them and carry on without. Project markdown is optional,
+/// so a project whose markdown fails is still listed from its entry. The
+/// screen stays up for at least `min_display_time` seconds. Returns the manifest.
+pub async fn run_loading_screen(terminal_state: &mut TerminalState, material: &Material, input_context: &mut InputContext, min_display_time: f32) -> Vec<AssetEntry> {
+    let mut files = Vec::new();
+    if content::wants_bundle() {
+        files.push(LoadingFile::start(BUNDLE_PATH, false, LoadTarget::Bundle));
+    }
+    let mut step = LoadStep::Bundle;
+    let mut manifest = Vec::new();
+    let mut banner_height = setup_loading_state(terminal_state, files.len());
+
+    let mut current_frame_time = 0f32;
+    let mut shown_for = 0f32;
     let mut animation_step = 0;
     let mut time = 0f32;
+    let mut mouse_context = MouseContext::default();
+    // The banner as it was before being padded for an error dialog.
+    let mut banner_before_error: Option<Vec<Vec<Cell>>> = None;
 
-    let mut downloading_coroutine = start_file_coroutine(FILES_TO_LOAD[file_loading_index]);
     let (mut screen_w, mut screen_h) = screen_size();
     let mut term_render_target = render_target(screen_w as u32, screen_h as u32);
     term_render_target.texture.set_filter(FilterMode::Nearest);
 
-    while file_loading_index < FILES_TO_LOAD.len() {
+    while step != LoadStep::Projects || !files.iter().all(LoadingFile::is_settled) || !terminal_state.overlay.is_empty() || shown_for < min_display_time {
         let (new_screen_w, new_screen_h) = screen_size();
         if new_screen_w != screen_w || new_screen_h != screen_h {
             screen_h = new_screen_h;
@@ -75,6 +249,9 @@ pub async fn run_loading_screen(terminal_state: &mut TerminalState, material: &M
 
             term_render_target = render_target(screen_w as u32, screen_h as u32);
             term_render_target.texture.set_filter(FilterMode::Nearest);
+
+            update_grid_size(terminal_state);
+            fit_loading_font(terminal_state);
         }
 
         set_camera(&Camera2D {
@@ -83,68 +260,114 @@ pub async fn run_loading_screen(terminal_state: &mut TerminalState, material: &M
             render_target: Some(term_render_target.clone()),
             ..Default::default()
         });
-        
+
+        for file in files.iter_mut() {
+            let FileStatus::Loading(coroutine) = &file.status else {
+                continue;
+            };
+            if !coroutine.is_done() {
+                continue;
+            }
+
+            let result = coroutine.retrieve().expect("Future not done")
+                .map_err(|source| Error::Load { path: file.path.clone(), source })
+                .and_then(|bytes| {
+                    let size = bytes.len();
+                    file.apply(terminal_state, bytes).map(|_| size)
+                });
+
+            file.status = match result {
+                Ok(size) => {
+                    debug!("{} loaded!", file.path);
+                    FileStatus::Loaded(size)
+                }
+                // Development builds load the content directory without a bundle.
+                Err(Error::Load { .. }) if matches!(file.target, LoadTarget::Bundle) => {
+                    info!("No {}, loading files from the content directory", BUNDLE_PATH);
+                    FileStatus::Skipped
+                }
+                Err(error) if file.required => {
+                    terminal_state.overlay.show_load_error(&error);
+                    FileStatus::Failed(error.to_string())
+                }
+                Err(error) => {
+                    warn!("{}, carrying on without it", error);
+                    terminal_state.skipped_files.insert(file.path.clone());
+                    FileStatus::Failed(error.to_string())
+                }
+            };
+        }
+
+        if step != LoadStep::Projects && files.iter().all(LoadingFile::is_settled) && terminal_state.overlay.active_dialog().is_none() {
+            if step == LoadStep::Bundle {
+                // Read from the bundle, if there is one.
+                input_context.bindings = load_bindings().await;
+                manifest = load_manifest().await;
+                files.extend(manifest.iter().map(|entry| LoadingFile::start(&entry.path, entry.required, LoadTarget::Asset(entry.clone()))));
+                step = LoadStep::Assets;
+            }
+            else {
+                files.extend(project_list_entries(terminal_state).into_iter()
+                    .map(|entry| LoadingFile::start(&entry_markdown_path(&entry), false, LoadTarget::Project { entry, project: None })));
+                step = LoadStep::Projects;
+            }
+            banner_height = setup_loading_state(terminal_state, files.len());
+        }
+
+        if terminal_state.overlay.active_dialog().is_some() {
+            if banner_before_error.is_none() {
+                banner_before_error = Some(terminal_state.cell_buffer.clone());
+                make_room_for_dialog(terminal_state);
+            }
+
+            mouse::handle_mouse(screen_w, screen_h, false, &mut mouse_context, terminal_state);
+            input_context.update(terminal_state);
+            let clicked_button = match mouse_context.clicked {
+                Some(HitTarget::DialogButton(button)) => Some(button),
+                _ => None,
+            };
+            let focus_step = match () {
+                _ if input_context.is_pressed(Action::FocusLeft) || input_context.is_pressed(Action::Up) => -1,
+                _ if input_context.is_pressed(Action::FocusRight) || input_context.is_pressed(Action::Down) => 1,
+                _ => 0,
+            };
+            let answer = terminal_state.overlay.handle_dialog_input(clicked_button, input_context.is_pressed(Action::Select), input_context.is_pressed(Action::Back), focus_step);
+            mouse_context.reset();
+
+            if let Some((DialogKind::LoadError { path }, button)) = answer {
+                terminal_state.hover_rect = None;
+                if let Some(file) = files.iter_mut().find(|file| file.path == path) {
+                    file.status = match button {
+                        RETRY_BUTTON => FileStatus::Loading(start_file_coroutine(&file.path)),
+                        _ => FileStatus::Skipped,
+                    };
+                }
+                if button != RETRY_BUTTON {
+                    terminal_state.skipped_files.insert(path);
+                }
+            }
+        }
+        else if let Some(banner) = banner_before_error.take() {
+            terminal_state.cell_buffer = banner;
+            update_grid_size(terminal_state);
+            fit_loading_font(terminal_state);
+        }
+
         current_frame_time += get_frame_time();
+        shown_for += get_frame_time();
         if current_frame_time >= ANIM_TIME_STEP {
             current_frame_time = 0f32;
             animation_step =  (animation_step + 1) % BALLOON_SPINNER_CHARS.len();
-            //TODO: Animate
-            //
-            terminal_state.cell_buffer[animated_cell_pos.1][animated_cell_pos.0].char = BALLOON_SPINNER_CHARS[animation_step];
-
-            if animation_step == 0 {
-                if downloading_coroutine.is_done() {
-                    debug!("font {} loaded!", FILES_TO_LOAD[file_loading_index]);
-                    let bytes = downloading_coroutine.retrieve().expect("Future not done").expect("Issue downloading file");
-                    match file_loading_index {
-                        0 => {
-                            let new_font = load_ttf_font_from_bytes(&bytes).expect("");
-                            terminal_state.default_font = Some(new_font);
-                        },
-                        1 => {
-                            let new_font = load_ttf_font_from_bytes(&bytes).expect("");
-                            terminal_state.resume_bold_font = Some(new_font);
-                        }
-                        2 => {
-                            let new_font = load_ttf_font_from_bytes(&bytes).expect("");
-                            terminal_state.resume_italic_bold_font = Some(new_font);
-                        }
-                        3 => {
-                            let new_font = load_ttf_font_from_bytes(&bytes).expect("");
-                            terminal_state.resume_italic_font = Some(new_font);
-                        }
-                        4 => {
-                            if let Ok(new_font) = load_ttf_font_from_bytes(&bytes) {
-                                terminal_state.resume_normal_font = Some(new_font);
-                            }
-                            else {
-                                error!("Could not load font: {:?}", bytes);
-                            }
-                        }
-                        5 => {
-                            let project_data: Vec<ProjectInfo> = serde_json::from_slice(&bytes).expect("Could not decode json");
-                            terminal_state.projects = project_data;
-                        }
-                        _ => ()//panic!("Font loading index not supported")
-                    }
-                    file_loading_index += 1;
-
-                    if file_loading_index < FILES_TO_LOAD.len() {
-                        downloading_coroutine = start_file_coroutine(FILES_TO_LOAD[file_loading_index]);
-                        terminal_state.cell_buffer.pop();
-                        let buffer_width = terminal_state.cell_buffer[0].len();
-                        let padding = " ".repeat((buffer_width - FILES_TO_LOAD[file_loading_index].len()) / 2);
-                        let new_file_line = generate_cell_line(&format!("{}{}", padding, FILES_TO_LOAD[file_loading_index]));
-                        terminal_state.cell_buffer.push(new_file_line);
-                    }
-                }
-            }
         }
+        write_status_lines(terminal_state, banner_height, &files, BALLOON_SPINNER_CHARS[animation_step]);
+
+        terminal_state.overlay.update(get_frame_time());
+        terminal_state.overlay_buffer = overlay::compose(terminal_state, &input_context.bindings);
 
         clear_background(DARKGRAY);
-        draw_terminal_cells(terminal_state, None);
+        draw_terminal_cells(terminal_state);
         set_default_camera();
-        gl_use_material(&material);
+        gl_use_material(material);
         material.set_uniform("iTime", time);
         time += 0.02;
 
@@ -163,4 +386,12 @@ pub async fn run_loading_screen(terminal_state: &mut TerminalState, material: &M
         next_frame().await;
     }
 
+    terminal_state.overlay_buffer = None;
+
+    let projects = files.into_iter().filter_map(|file| match file.target {
+        LoadTarget::Project { entry, project } => project.map(|project| *project).or_else(|| project_without_markdown(&entry)),
+        _ => None,
+    }).collect();
+    set_projects(terminal_state, projects);
+    manifest
 }
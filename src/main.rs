@@ -1,10 +1,16 @@
-
 use macroquad::{miniquad::window::{dpi_scale, screen_size}, prelude::*};
-use models::{Cell, FontType, TermSubState, TerminalState};
+use assets::AssetKey;
+use models::{BorderStyle, Cell, FontType, TermSubState, TerminalState};
 use opener::open_url;
-use projects::{setup_projects, update_project_buffer};
-use resume::{setup_resume, update_resume_buffer};
-use terminal_templates::{generate_highlight_box, BALLOON_SPINNER, BALLOON_SPINNER_CHARS, LOAD_TEMPLATE};
+use projects::{animate_project_art, setup_projects, update_project_buffer};
+use resume::{layout_resume, setup_resume, update_resume_buffer, RESUME_PATH};
+use terminal_templates::{generate_highlight_box, MAIN_TEMPLATE};
+use mouse::{HitTarget, MouseContext};
+use touch::{Swipe, TouchContext};
+use input::{Action, InputContext};
+use error::Result;
+use overlay::{Dialog, DialogKind, RETRY_BUTTON};
+use palette::CommandPalette;
 use ui::UiContext;
 use utils::{highlight_cells, overflow_sub, reset_all_highlights};
 use std::{cmp::min, default::Default};
@@ -19,6 +25,76 @@ mod projects;
 mod utils;
 mod resume;
 mod markdown_renderer;
+mod grid;
+mod layout;
+mod mouse;
+mod selection;
+mod clipboard;
+mod touch;
+mod gamepad;
+mod window_focus;
+mod input;
+mod help;
+mod palette;
+mod themes;
+mod overlay;
+mod error;
+mod assets;
+mod render_cache;
+mod bundle;
+mod content;
+#[cfg(not(target_arch = "wasm32"))]
+mod content_files;
+mod hot_reload;
+mod front_matter;
+mod ascii_image;
+#[cfg(not(target_arch = "wasm32"))]
+mod check;
+
+pub const CONTACT_URL: &str = "mailto:camilomcatasus@gmail.com";
+/// Seconds the loading screen stays up at least, so it doesn't just flash past when files are cached.
+const MIN_LOADING_SCREEN_TIME: f32 = 0.5;
+
+/// Asks how to get in touch before leaving the page for a mail app.
+pub fn show_contact_dialog(terminal_state: &mut TerminalState) {
+    let address = CONTACT_URL.trim_start_matches("mailto:");
+    terminal_state.overlay.show_dialog(Dialog::new(
+        DialogKind::Contact,
+        "contact",
+        &format!("Send an email to {}?", address),
+        &["Open", "Copy address", "Cancel"],
+    ));
+}
+
+/// Acts on the button a dialog was answered with.
+async fn answer_dialog(terminal_state: &mut TerminalState, kind: DialogKind, button: usize) -> Result<()> {
+    match (kind, button) {
+        (DialogKind::Contact, 0) => {
+            open_url(CONTACT_URL);
+            terminal_state.overlay.toast("Opening mail app...");
+        }
+        (DialogKind::Contact, 1) => {
+            clipboard::copy_to_clipboard(CONTACT_URL.trim_start_matches("mailto:"));
+            terminal_state.overlay.toast("Address copied");
+        }
+        (DialogKind::Contact, _) => (),
+        // Whatever needed the file is opened again, which loads it again.
+        (DialogKind::LoadError { path }, RETRY_BUTTON) => {
+            terminal_state.skipped_files.remove(&path);
+            if path == RESUME_PATH {
+                setup_resume(terminal_state).await?;
+            }
+            else if matches!(terminal_state.sub_state, TermSubState::Projects { .. }) {
+                update_project_buffer(terminal_state)?;
+            }
+        }
+        (DialogKind::LoadError { path }, _) => {
+            terminal_state.skipped_files.insert(path);
+        }
+        (DialogKind::ProjectLinks { project_index }, button) => projects::open_project_link(terminal_state, project_index, button),
+    }
+    Ok(())
+}
 
 fn window_conf() -> Conf {
     Conf {
@@ -29,8 +105,21 @@ fn window_conf() -> Conf {
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
+/// `--check` validates the content and exits instead of opening the site.
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    if std::env::args().any(|arg| arg == "--check") {
+        return check::run_check();
+    }
+    macroquad::Window::from_config(window_conf(), run());
+    std::process::ExitCode::SUCCESS
+}
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    macroquad::Window::from_config(window_conf(), run());
+}
+
+async fn run() {
     let (mut screen_w,mut screen_h) = screen_size();
     dpi_scale();
 
@@ -41,13 +130,15 @@ async fn main() {
     term_render_target.texture.set_filter(FilterMode::Nearest);
 
     let mut terminal_state = TerminalState {
-        default_font: None,
         projects: Vec::new(),
         font_size: 38f32,
+        font_scale: 1f32,
+        border_style: BorderStyle::Light,
         terminal_width_px: screen_w,
         terminal_height_px: screen_h,
         ..Default::default()
     };
+    grid::update_grid_size(&mut terminal_state);
 
     let mut material_params = MaterialParams::default();
 
@@ -61,13 +152,17 @@ async fn main() {
         material_params,
     ).unwrap();
 
-    loading_screen::run_loading_screen(&mut terminal_state, &material).await;
+    let mut input_context = InputContext::default();
+    let manifest = loading_screen::run_loading_screen(&mut terminal_state, &material, &mut input_context, MIN_LOADING_SCREEN_TIME).await;
+    let mut content_watcher = hot_reload::ContentWatcher::new();
     setup_main_state(&mut terminal_state);
 
     let mut ui_context = ui::UiContext::default();
+    let mut mouse_context = MouseContext::default();
+    let mut touch_context = TouchContext::default();
     let mut time = 0.1f32;
-    let ui_skin = ui::create_ui_skin(terminal_state.default_font.as_ref().unwrap());
-    let button_skin = ui::button_ui_skin(terminal_state.default_font.as_ref().unwrap());
+    let ui_skin = ui::create_ui_skin(terminal_state.assets.font(AssetKey::TerminalFont));
+    let button_skin = ui::button_ui_skin(terminal_state.assets.font(AssetKey::TerminalFont));
     loop {
         let (new_screen_w, new_screen_h) = screen_size();
         if new_screen_w != screen_w || new_screen_h != screen_h {
@@ -77,12 +172,45 @@ async fn main() {
             term_render_target = render_target(screen_w as u32, screen_h as u32);
             term_render_target.texture.set_filter(FilterMode::Nearest);
             debug!("New screen size: {:?}", (screen_w, screen_h));
+
+            if grid::update_grid_size(&mut terminal_state) {
+                debug!("New grid size: {:?}", (terminal_state.term_width, terminal_state.term_height));
+                if let Err(error) = relayout(&mut terminal_state).await {
+                    terminal_state.overlay.show_load_error(&error);
+                }
+            }
         }
 
         set_default_camera();
-        ui::handle_ui(screen_w, screen_h, &mut ui_context, &ui_skin, &button_skin, &mut terminal_state);
-        handle_input(&mut terminal_state, &ui_context).await;
+        ui::handle_ui(screen_w, screen_h, &mut ui_context, &ui_skin, &button_skin, !touch_context.in_use, &mut terminal_state);
+        touch::handle_touch(screen_w, screen_h, &mut touch_context, &mut terminal_state);
+        if touch_context.font_scale_changed && grid::update_grid_size(&mut terminal_state) {
+            if let Err(error) = relayout(&mut terminal_state).await {
+                terminal_state.overlay.show_load_error(&error);
+            }
+        }
+        mouse::handle_mouse(screen_w, screen_h, touch_context.in_use, &mut mouse_context, &mut terminal_state);
+        selection::handle_selection_keys(&mut terminal_state);
+        input_context.update(&terminal_state);
+        if let Err(error) = handle_input(&mut terminal_state, &ui_context, &mouse_context, &touch_context, &input_context).await {
+            terminal_state.overlay.show_load_error(&error);
+        }
+        let changed_files = content_watcher.changed_files(get_frame_time());
+        if !changed_files.is_empty() {
+            hot_reload::reload_content(&mut terminal_state, &manifest, &changed_files).await;
+        }
+        if terminal_state.project_files.poll() && matches!(terminal_state.sub_state, TermSubState::Projects { .. }) {
+            if let Err(error) = update_project_buffer(&mut terminal_state) {
+                terminal_state.overlay.show_load_error(&error);
+            }
+        }
+        animate_scrolling(&mut terminal_state, input_context.bindings.smooth_scrolling);
+        animate_project_art(&mut terminal_state, get_frame_time());
+        terminal_state.overlay.update(get_frame_time());
+        terminal_state.overlay_buffer = overlay::compose(&terminal_state, &input_context.bindings);
         ui_context.reset();
+        mouse_context.reset();
+        touch_context.reset();
 
         set_camera(&Camera2D {
             zoom: vec2(1./(screen_w /2f32), 1./(screen_h / 2f32)),
@@ -91,8 +219,8 @@ async fn main() {
             ..Default::default()
         });
 
-        clear_background(DARKGRAY);
-        draw_terminal_cells(&mut terminal_state, Some(&ui_context));
+        clear_background(themes::theme(terminal_state.theme).background);
+        draw_terminal_cells(&terminal_state);
         set_default_camera();
         gl_use_material(&material);
         material.set_uniform("iTime", time);
@@ -114,108 +242,272 @@ async fn main() {
     }
 }
 
-const FONT_RATIO : f32 = 40.0 / 1440.0;
+const HOVER_COLOR: Color = Color::new(1f32, 1f32, 1f32, 0.25f32);
+const SELECTION_COLOR: Color = Color::new(0f32, 0.89f32, 0.19f32, 0.45f32);
+
+fn cell_font<'a>(terminal_state: &'a TerminalState, font_type: &FontType) -> Option<&'a Font> {
+    terminal_state.assets.font(match font_type {
+        FontType::Default => AssetKey::TerminalFont,
+        FontType::ResumeBold => AssetKey::ResumeBoldFont,
+        FontType::ResumeItalic => AssetKey::ResumeItalicFont,
+        FontType::ResumeDefault => AssetKey::ResumeFont,
+        FontType::ResumeItalicBold => AssetKey::ResumeBoldItalicFont,
+    })
+}
 
-pub fn draw_terminal_cells(terminal_state: &mut TerminalState, ui_context: Option<&UiContext>) {
+pub fn draw_terminal_cells(terminal_state: &TerminalState) {
     let (screen_w, screen_h) = screen_size();
+    let font_size = terminal_state.font_size;
+    let theme = themes::theme(terminal_state.theme);
+
+    let (horizontal_padding, vertical_padding) = grid::grid_padding(terminal_state);
+    let cell_left = |cell_x: usize| horizontal_padding + cell_x as f32 * font_size / 2f32 - screen_w / 2f32;
+    // Rows are fractional so panels can be drawn part way through a scroll.
+    let baseline = |row: f32| vertical_padding + row * font_size - screen_h / 2f32;
+
+    let draw_cell_background = |cell_x: usize, row: f32, background_color: Color| {
+        draw_rectangle(cell_left(cell_x),
+            baseline(row - 1f32) + (font_size / 5f32),
+            font_size / 2f32,
+            font_size,
+            theme.map(background_color));
+    };
+    let draw_cell_text = |cell_x: usize, row: f32, cell: &Cell| {
+        draw_text_ex(&cell.char.to_string(), cell_left(cell_x), baseline(row), TextParams {
+            font: cell_font(terminal_state, &cell.font_type),
+            font_size: font_size as u16,
+            color: theme.map(*cell.foreground_color),
+            ..Default::default()
+        });
+    };
 
-    let mut font = terminal_state.default_font.as_ref();
-
-    let mut previous_font_type = &FontType::Default;
-    
-    let term_col_count = terminal_state.cell_buffer[0].len() as f32;
-    let term_row_count = terminal_state.cell_buffer.len() as f32;
-
-    terminal_state.font_size = screen_h * FONT_RATIO;
-
-    let vertical_padding = (screen_h - term_row_count * terminal_state.font_size) / 2f32;
-    let horizontal_padding = (screen_w - term_col_count * terminal_state.font_size / 2f32) / 2f32;
+    let cell_buffer = terminal_state.overlay_buffer.as_ref().unwrap_or(&terminal_state.cell_buffer);
+    // Help and the palette cover the screen, so nothing under them can be hovered
+    // or selected. Dialogs keep the hover for their buttons.
+    let show_hover = !terminal_state.help_visible && terminal_state.palette.is_none();
+    let show_selection = !terminal_state.is_modal_open();
 
-    for (cell_y, cell_line) in terminal_state.cell_buffer.iter().enumerate() {
+    for (cell_y, cell_line) in cell_buffer.iter().enumerate() {
         for (cell_x, cell) in cell_line.iter().enumerate() {
             if let Some(background_color) = cell.background_color {
-                draw_rectangle(horizontal_padding + cell_x as f32 * terminal_state.font_size / 2f32 - screen_w / 2f32, 
-                    vertical_padding + (cell_y as f32 - 1f32) * terminal_state.font_size - screen_h / 2f32 + (terminal_state.font_size / 5f32), 
-                    terminal_state.font_size / 2f32, 
-                    terminal_state.font_size, 
-                    *background_color);
+                draw_cell_background(cell_x, cell_y as f32, *background_color);
+            }
+            if show_hover && terminal_state.hover_rect.is_some_and(|rect| rect.contains(vec2(cell_x as f32, cell_y as f32))) {
+                draw_cell_background(cell_x, cell_y as f32, HOVER_COLOR);
+            }
+            if show_selection && terminal_state.selection.is_some_and(|selection| selection.contains(cell_x, cell_y)) {
+                draw_cell_background(cell_x, cell_y as f32, SELECTION_COLOR);
             }
         }
     }
 
-    for (cell_y, cell_line) in terminal_state.cell_buffer.iter().enumerate() {
+    for (cell_y, cell_line) in cell_buffer.iter().enumerate() {
         for (cell_x, cell) in cell_line.iter().enumerate() {
+            draw_cell_text(cell_x, cell_y as f32, cell);
+        }
+    }
 
-            if &cell.font_type != previous_font_type {
-
-                font = match cell.font_type {
-                    FontType::Default => terminal_state.default_font.as_ref(),
-                    FontType::ResumeBold => terminal_state.resume_bold_font.as_ref(),
-                    FontType::ResumeItalic => terminal_state.resume_italic_font.as_ref(),
-                    FontType::ResumeDefault => terminal_state.resume_normal_font.as_ref(),
-                    FontType::ResumeItalicBold => terminal_state.resume_italic_bold_font.as_ref(),
-                };
+    if terminal_state.overlay_buffer.is_some() {
+        return;
+    }
 
-                previous_font_type = &cell.font_type;
-            }
-            
-            if let Some(inner_ui_context) = ui_context {
-                if inner_ui_context.back_pressed {
-                    let test = 0;
+    // Panels still catching up with a scroll are drawn again over their resting
+    // position, shifted by the part of a line they have left to travel.
+    for panel in terminal_state.sub_state.panels().iter().filter(|panel| panel.is_scrolling()) {
+        draw_rectangle(cell_left(panel.offset_x),
+            baseline(panel.offset_y as f32 - 1f32) + (font_size / 5f32),
+            panel.width as f32 * font_size / 2f32,
+            panel.height as f32 * font_size,
+            theme.background);
+
+        let first_line = panel.scroll_position.max(0f32).floor() as usize;
+        let line_offset = panel.scroll_position.max(0f32).fract();
+        let visible_lines = panel.fitted_buffer.iter().skip(first_line).take(panel.height + 1);
+        for (line_number, line) in visible_lines.enumerate() {
+            let row = (panel.offset_y + line_number) as f32 - line_offset;
+            for (x, cell) in line.iter().enumerate() {
+                if let Some(background_color) = cell.background_color {
+                    draw_cell_background(panel.offset_x + x, row, *background_color);
                 }
+                draw_cell_text(panel.offset_x + x, row, cell);
             }
+        }
 
-            let char_x = horizontal_padding + cell_x as f32 * terminal_state.font_size / 2f32 - screen_w / 2f32;
-            let char_y = vertical_padding + cell_y as f32 * terminal_state.font_size - screen_h / 2f32;
-            draw_text_ex(&cell.char.to_string(), char_x, char_y, TextParams {
-                font,
-                font_size: terminal_state.font_size as u16,
-                color: *cell.foreground_color,
-                ..Default::default()
-
-            });
+        // Lines sliding past the edges spill into the border rows, so those go back on top.
+        for border_y in [panel.offset_y.checked_sub(1), Some(panel.offset_y + panel.height)].into_iter().flatten() {
+            let Some(line) = cell_buffer.get(border_y) else {
+                continue;
+            };
+            let border_cells = line.iter().enumerate().skip(panel.offset_x.saturating_sub(1)).take(panel.width + 2);
+            for (x, cell) in border_cells {
+                draw_rectangle(cell_left(x), baseline(border_y as f32 - 1f32) + (font_size / 5f32), font_size / 2f32, font_size, theme.background);
+                if let Some(background_color) = cell.background_color {
+                    draw_cell_background(x, border_y as f32, *background_color);
+                }
+                draw_cell_text(x, border_y as f32, cell);
+            }
         }
     }
+}
+
+/// How quickly smooth scrolling closes the gap to the target line, per second.
+const SCROLL_SPEED: f32 = 18.0;
+
+/// Moves every panel's drawn scroll position towards its `index`.
+fn animate_scrolling(terminal_state: &mut TerminalState, smooth_scrolling: bool) {
+    let step = 1f32 - (-SCROLL_SPEED * get_frame_time()).exp();
+    for panel in terminal_state.sub_state.panels_mut() {
+        let distance = panel.index as f32 - panel.scroll_position;
+        panel.scroll_position = match smooth_scrolling && distance.abs() > 0.01 {
+            true => panel.scroll_position + distance * step,
+            false => panel.index as f32,
+        };
+    }
+}
 
+/// Top-left cell of the main menu template once it is centered in the grid.
+fn main_template_origin(terminal_state: &TerminalState) -> (usize, usize) {
+    let template_width = MAIN_TEMPLATE.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    (
+        terminal_state.term_width.saturating_sub(template_width) / 2,
+        terminal_state.term_height.saturating_sub(MAIN_TEMPLATE.len()) / 2,
+    )
 }
 
 pub fn setup_main_state(terminal_state: &mut TerminalState) {
 
     terminal_state.sub_state = TermSubState::Main { index: 0 };
 
-    terminal_state.font_size = 48.;
-    terminal_state.cell_buffer = terminal_templates::MAIN_TEMPLATE.iter().map(|line| {
-        line.chars().map(|c| {
-            Cell {
+    let (origin_x, origin_y) = main_template_origin(terminal_state);
+    let template_width = MAIN_TEMPLATE.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let buffer_width = usize::max(terminal_state.term_width, template_width);
+    let buffer_height = usize::max(terminal_state.term_height, MAIN_TEMPLATE.len());
+
+    terminal_state.cell_buffer = vec![vec![Cell::default(); buffer_width]; buffer_height];
+    for (line_index, line) in MAIN_TEMPLATE.iter().enumerate() {
+        for (char_index, c) in line.chars().enumerate() {
+            terminal_state.cell_buffer[origin_y + line_index][origin_x + char_index] = Cell {
                 char: c,
                 background_color: None,
                 foreground_color: &GREEN,
                 font_type: FontType::Default
-            }
-        }).collect()
-    }).collect();
-    terminal_state.line_buffer = terminal_templates::MAIN_TEMPLATE
+            };
+        }
+    }
+    terminal_state.line_buffer = MAIN_TEMPLATE
         .to_vec().iter().map(|val| val.to_string()).collect();
 
-    let high_light_box = generate_highlight_box(1).expect("Problem generating highlight box from main template");
-    highlight_cells(&high_light_box, terminal_state, &WHITE)
-    //terminal_state.highlighted_boxes = vec![generate_highlight_box(1).expect("Should return a box")];
+    highlight_main_index(terminal_state, 0);
+}
 
+/// Cells covered by the main menu entry at `index`, in buffer coordinates.
+pub fn main_item_box(terminal_state: &TerminalState, index: usize) -> Option<Rect> {
+    let mut item_box = generate_highlight_box(index + 1)?;
+    let (origin_x, origin_y) = main_template_origin(terminal_state);
+    item_box.x += origin_x as f32;
+    item_box.y += origin_y as f32;
+    Some(item_box)
 }
 
-async fn handle_input(terminal_state: &mut TerminalState, ui_context: &UiContext) {
-    let down_input = is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) || ui_context.down_pressed || mouse_wheel().1 < 0.0;
-    let up_input = is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) || ui_context.up_pressed || mouse_wheel().1 > 0.0;
-    let left_pressed = is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) || ui_context.left_pressed;
-    let right_pressed = is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) || ui_context.right_pressed;
-    let enter_pressed = is_key_pressed(KeyCode::Enter) || ui_context.enter_pressed;
-    let back_pressed = is_key_pressed(KeyCode::Backspace);
+fn highlight_main_index(terminal_state: &mut TerminalState, index: usize) {
+    reset_all_highlights(terminal_state);
+    let highlight_box = main_item_box(terminal_state, index).expect("Problem generating highlight box from main template");
+    highlight_cells(&highlight_box, terminal_state, &WHITE);
+}
+
+/// Rebuilds the current screen after the grid size or border style changed.
+pub async fn relayout(terminal_state: &mut TerminalState) -> Result<()> {
+    match terminal_state.sub_state {
+        TermSubState::Main { index } => {
+            setup_main_state(terminal_state);
+            terminal_state.sub_state = TermSubState::Main { index };
+            highlight_main_index(terminal_state, index);
+        }
+        TermSubState::Projects { .. } => update_project_buffer(terminal_state)?,
+        TermSubState::Resume(_) => layout_resume(terminal_state),
+        _ => ()
+    }
+    Ok(())
+}
+
+/// Where `index` ends up after the paging actions pressed this frame, given
+/// how far a page goes and the largest index allowed.
+fn jump_index(index: usize, input_context: &InputContext, page: usize, max: usize) -> usize {
+    if input_context.is_pressed(Action::Home) {
+        0
+    }
+    else if input_context.is_pressed(Action::End) {
+        max
+    }
+    else if input_context.is_pressed(Action::PageUp) {
+        index.saturating_sub(page)
+    }
+    else if input_context.is_pressed(Action::PageDown) {
+        min(index + page, max)
+    }
+    else {
+        index
+    }
+}
+
+async fn handle_input(terminal_state: &mut TerminalState, ui_context: &UiContext, mouse_context: &MouseContext, touch_context: &TouchContext, input_context: &InputContext) -> Result<()> {
+    let down_input = input_context.is_pressed(Action::Down) || ui_context.down_pressed || mouse_wheel().1 < 0.0;
+    let up_input = input_context.is_pressed(Action::Up) || ui_context.up_pressed || mouse_wheel().1 > 0.0;
+    // Swiping left pulls in the panel on the right, like turning a page.
+    let left_pressed = input_context.is_pressed(Action::FocusLeft) || ui_context.left_pressed || touch_context.swipe == Some(Swipe::Right);
+    let right_pressed = input_context.is_pressed(Action::FocusRight) || ui_context.right_pressed || touch_context.swipe == Some(Swipe::Left);
+    let enter_pressed = input_context.is_pressed(Action::Select) || ui_context.enter_pressed;
+    let back_pressed = input_context.is_pressed(Action::Back) || ui_context.back_pressed;
+    let clicked = mouse_context.clicked.or(touch_context.tapped);
+    let scroll_lines = touch_context.scroll_lines;
 
     //Check to see if we need to handle input
-    if !(down_input || up_input || left_pressed || right_pressed || enter_pressed || back_pressed) {
-        return;
+    if !(down_input || up_input || left_pressed || right_pressed || enter_pressed || back_pressed || clicked.is_some() || scroll_lines != 0 || input_context.any_pressed()) {
+        return Ok(());
     }
 
+    // A dialog keeps all input until it is answered.
+    if terminal_state.overlay.active_dialog().is_some() {
+        let clicked_button = match clicked {
+            Some(HitTarget::DialogButton(button)) => Some(button),
+            _ => None,
+        };
+        let focus_step = match () {
+            _ if left_pressed || up_input => -1,
+            _ if right_pressed || down_input => 1,
+            _ => 0,
+        };
+        if let Some((kind, button)) = terminal_state.overlay.handle_dialog_input(clicked_button, enter_pressed, back_pressed, focus_step) {
+            answer_dialog(terminal_state, kind, button).await?;
+        }
+        return Ok(());
+    }
+
+    // Help covers the screen, so the first input after opening it only closes it.
+    if terminal_state.help_visible {
+        terminal_state.help_visible = false;
+        return Ok(());
+    }
+    if input_context.is_pressed(Action::Help) {
+        terminal_state.help_visible = true;
+        return Ok(());
+    }
+
+    if terminal_state.palette.is_some() {
+        match clicked {
+            Some(_) => terminal_state.palette = None,
+            None => palette::handle_palette_input(terminal_state, input_context).await?,
+        }
+        return Ok(());
+    }
+    if input_context.is_pressed(Action::Palette) {
+        terminal_state.palette = Some(CommandPalette::default());
+        return Ok(());
+    }
+
+    // Navigating changes what is on screen, so an old selection would point at the wrong text.
+    terminal_state.selection = None;
+
     let project_count = terminal_state.projects.len();
 
     let mut new_main_index : Option<usize> = None;
@@ -224,7 +516,12 @@ async fn handle_input(terminal_state: &mut TerminalState, ui_context: &UiContext
     match &mut terminal_state.sub_state {
         TermSubState::Main { ref mut index } => {
             let mut index_changed = false;
-            if down_input {
+            let mut enter_pressed = enter_pressed;
+            if let Some(HitTarget::MainItem(clicked_index)) = clicked {
+                *index = clicked_index;
+                enter_pressed = true;
+            }
+            else if down_input {
                 index_changed = true;
                 *index = (*index + 1) % 3;
             }
@@ -237,36 +534,94 @@ async fn handle_input(terminal_state: &mut TerminalState, ui_context: &UiContext
                     *index -= 1;
                 }
             }
+            else if input_context.is_pressed(Action::Home) || input_context.is_pressed(Action::End) {
+                index_changed = true;
+                *index = jump_index(*index, input_context, 1, 2);
+            }
             if index_changed {
                 new_main_index = Some(*index);
             }
             else if enter_pressed {
                 match index {
                     0 => {
-                        setup_projects(terminal_state).await;
+                        setup_projects(terminal_state)?;
                     }
                     1 => {
                         info!("Trying to setup resume");
 
-                        setup_resume(terminal_state).await;
+                        setup_resume(terminal_state).await?;
                     }
                     2 => {
-                        open_url("mailto:camilomcatasus@gmail.com");
+                        highlight_main_index(terminal_state, 2);
+                        show_contact_dialog(terminal_state);
                     }
                     _ => panic!("")
 
                 };
-                return;
+                return Ok(());
             }
         }
-        TermSubState::Projects { ref mut selected_project_index, ref mut main_focus, ref mut project_about_scroll,..} => {
+        TermSubState::Projects { ref mut selected_project_index, ref mut main_focus, ref mut project_about_scroll, ref mut search, ref cell_panels } => {
+            if let Some(query) = search {
+                let query_changed = !input_context.typed.is_empty() || input_context.is_pressed(Action::Erase);
+                query.extend(&input_context.typed);
+                if input_context.is_pressed(Action::Erase) {
+                    query.pop();
+                }
+
+                let matches = projects::matching_projects(&terminal_state.projects, query);
+                let current = *selected_project_index;
+                let next_match = if query_changed {
+                    matches.first()
+                }
+                else if down_input {
+                    matches.iter().find(|index| **index > current).or(matches.first())
+                }
+                else if up_input {
+                    matches.iter().rev().find(|index| **index < current).or(matches.last())
+                }
+                else {
+                    None
+                };
+
+                if let Some(next_match) = next_match {
+                    *selected_project_index = *next_match;
+                }
+                if enter_pressed || back_pressed {
+                    *search = None;
+                }
+
+                update_project_buffer(terminal_state)?;
+                return Ok(());
+            }
+
+            if input_context.is_pressed(Action::OpenLinks) {
+                let project_index = *selected_project_index;
+                projects::open_project_links(terminal_state, project_index);
+                return Ok(());
+            }
+
+            if input_context.is_pressed(Action::Search) {
+                *search = Some(String::new());
+                *main_focus = false;
+            }
+
             if back_pressed {
                 setup_main_state(terminal_state);
-                return;
+                return Ok(());
+            }
+            match clicked {
+                Some(HitTarget::Project(clicked_index)) => {
+                    *selected_project_index = clicked_index;
+                    *main_focus = false;
+                }
+                Some(HitTarget::AboutPanel) => *main_focus = true,
+                _ => ()
             }
+
             if !*main_focus {
                 if up_input {
-                    *selected_project_index = overflow_sub(&selected_project_index, project_count);
+                    *selected_project_index = overflow_sub(selected_project_index, project_count);
                 }
                 if down_input {
                     *selected_project_index = (*selected_project_index + 1) % project_count;
@@ -281,6 +636,19 @@ async fn handle_input(terminal_state: &mut TerminalState, ui_context: &UiContext
                     *project_about_scroll += 1;
                 }
             }
+
+            if *main_focus {
+                let about_panel = &cell_panels[projects::ABOUT_PANEL_INDEX];
+                let max_scroll = about_panel.max_index();
+                let scroll = jump_index(*project_about_scroll, input_context, about_panel.height, max_scroll);
+                *project_about_scroll = min(scroll.saturating_add_signed(scroll_lines), max_scroll);
+            }
+            else {
+                let last_project = project_count.saturating_sub(1);
+                let page = cell_panels[projects::PROJECTS_PANEL_INDEX].height;
+                let index = jump_index(*selected_project_index, input_context, page, last_project);
+                *selected_project_index = min(index.saturating_add_signed(scroll_lines), last_project);
+            }
             if left_pressed {
                 *main_focus = true;
             }
@@ -288,21 +656,22 @@ async fn handle_input(terminal_state: &mut TerminalState, ui_context: &UiContext
                 *main_focus = false;
             }
 
-            update_project_buffer(terminal_state).await;
+            update_project_buffer(terminal_state)?;
         }
         TermSubState::Resume(ref mut resume_panel) => {
             if back_pressed {
                 setup_main_state(terminal_state);
-                return;
+                return Ok(());
             }
 
-            if up_input && resume_panel.index != 0 {
-                resume_panel.index = resume_panel.index.saturating_sub(3);
+            if up_input {
+                resume_panel.scroll_by(-3);
             }
-            if down_input && resume_panel.index != resume_panel.fitted_buffer.len() - resume_panel.height - 1 {
-                resume_panel.index += 3;
-                resume_panel.index = min(resume_panel.index, resume_panel.fitted_buffer.len() - resume_panel.height - 1);
+            if down_input {
+                resume_panel.scroll_by(3);
             }
+            resume_panel.index = jump_index(resume_panel.index, input_context, resume_panel.height, resume_panel.max_index());
+            resume_panel.scroll_by(scroll_lines);
 
             update_resume_buffer(terminal_state);
 
@@ -313,14 +682,13 @@ async fn handle_input(terminal_state: &mut TerminalState, ui_context: &UiContext
     }
 
     if let Some(index) = new_main_index {
-        reset_all_highlights(terminal_state);
-        let highlight_box = generate_highlight_box(index + 1).expect("Couldn't create highlight box");
-        highlight_cells(&highlight_box, terminal_state, &WHITE);
+        highlight_main_index(terminal_state, index);
     }
+    Ok(())
 }
 
 
-const CRT_FRAGMENT_SHADER: &'static str = r#"#version 100
+const CRT_FRAGMENT_SHADER: &str = r#"#version 100
 precision lowp float;
 
 varying vec4 color;
@@ -369,7 +737,7 @@ void main() {
 }
 "#;
 
-const CRT_VERTEX_SHADER: &'static str = "#version 100
+const CRT_VERTEX_SHADER: &str = "#version 100
 attribute vec3 position;
 attribute vec2 texcoord;
 attribute vec4 color0;
@@ -1,4 +1,4 @@
-use std::default;
+use std::collections::HashMap;
 
 use macroquad::prelude::*;
 use crate::models::{
@@ -6,6 +6,8 @@ use crate::models::{
     Cell
 };
 use crate::FontType;
+use crate::ascii_image::{fit_size, image_to_art, ArtStyle};
+use crate::front_matter::split_front_matter;
 
 #[derive(PartialEq, Eq, Debug)]
 enum Token {
@@ -15,30 +17,67 @@ enum Token {
     NewLine,
     Space,
     PoundSign,
+    /// An image on a line of its own.
+    Image { alt: String, path: String, style: ArtStyle },
 }
 
-impl ToString for Token {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
-            Token::Space => String::from(" "),
-            Token::NewLine => String::from("\n"),
-            Token::Asterisk => String::from("*"),
-            Token::DoubleAsterisk => String::from("**"),
-            Token::PoundSign => String::from("#"),
-            Token::Word(str) => str.clone()
+            Token::Space => write!(f, " "),
+            Token::NewLine => writeln!(f),
+            Token::Asterisk => write!(f, "*"),
+            Token::DoubleAsterisk => write!(f, "**"),
+            Token::PoundSign => write!(f, "#"),
+            Token::Word(str) => write!(f, "{}", str),
+            Token::Image { alt, path, .. } => write!(f, "![{}]({})", alt, path),
         }
     }
 }
 
-fn lexer(mark_down_text: &String) -> Vec<Token> {
+/// An image written on a line of its own, `![alt](path)`. Giving it the
+/// title `"color"`, as in `![alt](path "color")`, draws it in half blocks
+/// with its own colours instead of the theme's.
+pub struct ImageLine<'a> {
+    pub alt: &'a str,
+    pub path: &'a str,
+    pub style: ArtStyle,
+}
+
+pub fn image_line(line: &str) -> Option<ImageLine<'_>> {
+    let (alt, target) = line.trim().strip_prefix("![")?.strip_suffix(')')?.split_once("](")?;
+    let (path, style) = match target.split_once(' ') {
+        Some((path, "\"color\"")) => (path, ArtStyle::HalfBlock),
+        Some(_) => return None,
+        None => (target, ArtStyle::Ramp),
+    };
+    (!path.is_empty()).then_some(ImageLine { alt, path, style })
+}
+
+/// Paths of the images a markdown document shows, as written in it.
+pub fn image_paths(mark_down_text: &str) -> Vec<&str> {
+    let (_, mark_down_text) = split_front_matter(mark_down_text);
+    mark_down_text.lines().filter_map(image_line).map(|image| image.path).collect()
+}
+
+fn lexer(mark_down_text: &str) -> Vec<Token> {
     let mut tokens : Vec<Token> = Vec::new();
 
     let mut pointer = 0;
 
     //debug!("Started lexing text: \n{}", mark_down_text);
-    while pointer < mark_down_text.len() - 1 {
-        let mut at1 = &mark_down_text[pointer..(pointer+1)];
-        let mut at2 = &mark_down_text[(pointer + 1)..(pointer + 2)];
+    while pointer < mark_down_text.len() {
+        if pointer == 0 || mark_down_text.as_bytes()[pointer - 1] == b'\n' {
+            let line_end = mark_down_text[pointer..].find('\n').map_or(mark_down_text.len(), |end| pointer + end);
+            if let Some(image) = image_line(&mark_down_text[pointer..line_end]) {
+                tokens.push(Token::Image { alt: image.alt.to_string(), path: image.path.to_string(), style: image.style });
+                pointer = line_end;
+                continue;
+            }
+        }
+
+        let at1 = mark_down_text.get(pointer..(pointer + 1)).unwrap_or("");
+        let at2 = mark_down_text.get((pointer + 1)..(pointer + 2)).unwrap_or("");
 
         let new_token = match (at1, at2) {
             ("#", _) => Token::PoundSign,
@@ -48,22 +87,23 @@ fn lexer(mark_down_text: &String) -> Vec<Token> {
             ("\r", "\n") => {pointer += 1; Token::NewLine},
             ("\n", _) =>  Token::NewLine,
             _ => {
-                //parse word
+                //parse word up to the next character that means something, keeping \# and \* in it
+                let bytes = mark_down_text.as_bytes();
+                let ends_word = |index: usize| match bytes[index] {
+                    b'#' | b'*' | b' ' | b'\n' => true,
+                    b'\r' => bytes.get(index + 1) == Some(&b'\n'),
+                    _ => false,
+                };
                 let start = pointer;
-                while pointer < mark_down_text.len() - 1 && !(at1 == "#" || at1 == "*" || at1 == " " || at1 == "\n" || at1 =="\r") {
-                    at1 = &mark_down_text[pointer..(pointer+1)];
-                    at2 = &mark_down_text[(pointer + 1)..(pointer + 2)];
-
-                    if at1 == "\\" && (at2 == "#" || at2 == "*") {
+                while pointer < bytes.len() && (pointer == start || !ends_word(pointer)) {
+                    if bytes[pointer] == b'\\' && matches!(bytes.get(pointer + 1), Some(b'#' | b'*')) {
                         pointer += 1;
                     }
                     pointer += 1;
                 }
-                pointer -= 1;
                 let word_text = mark_down_text[start..pointer].to_string();
                 pointer -= 1;
                 Token::Word(word_text)
-                
             }
         };
         pointer += 1;
@@ -73,7 +113,11 @@ fn lexer(mark_down_text: &String) -> Vec<Token> {
 
     tokens
 }
-pub fn render_markdown(mark_down_text: &String, width: usize, height: usize) -> CellPanel {
+/// Renders markdown into a panel, leaving out any front matter at the top.
+/// `images` are the decoded images by the path the markdown gives them,
+/// images that aren't there are shown as their alt text.
+pub fn render_markdown(mark_down_text: &str, images: &HashMap<&str, &Image>, width: usize, height: usize) -> CellPanel {
+    let (_, mark_down_text) = split_front_matter(mark_down_text);
     let mark_down_tokens = lexer(mark_down_text);
 
     //debug!("Tokens: {:?}", mark_down_tokens);
@@ -81,6 +125,7 @@ pub fn render_markdown(mark_down_text: &String, width: usize, height: usize) ->
     let mut token_index = 0;
     let mut generator = CellGenerator {
         line_width: width,
+        image_height: height,
         ..Default::default()
     };
 
@@ -109,7 +154,7 @@ pub fn render_markdown(mark_down_text: &String, width: usize, height: usize) ->
 
             }
             Token::PoundSign => {
-                if (token_index == 0 || mark_down_tokens[token_index - 1] == Token::NewLine) && mark_down_tokens[token_index + 1] == Token::Space {
+                if (token_index == 0 || mark_down_tokens[token_index - 1] == Token::NewLine) && mark_down_tokens.get(token_index + 1) == Some(&Token::Space) {
                     token_index += 1;
                     generator.header = true;
 
@@ -121,16 +166,21 @@ pub fn render_markdown(mark_down_text: &String, width: usize, height: usize) ->
                 generator.gen_cell(' ');
             }
             Token::Word(word) => {
-                if word.len() + generator.cell_line.len() >= generator.line_width {
+                if word.chars().count() + generator.cell_line.len() >= generator.line_width {
                     generator.cell_buffer.push(generator.cell_line);
+                    generator.wrapped.push(true);
                     generator.cell_line = Vec::new();
                 }
                 word.chars().for_each(|c| {
                     generator.gen_cell(c);
                 })
             }
+            Token::Image { alt, path, style } => {
+                generator.gen_image(alt, images.get(path.as_str()).copied(), *style);
+            }
             Token::NewLine => {
                 generator.cell_buffer.push(generator.cell_line);
+                generator.wrapped.push(false);
                 generator.cell_line = Vec::new();
                 generator.header = false;
             }
@@ -139,10 +189,17 @@ pub fn render_markdown(mark_down_text: &String, width: usize, height: usize) ->
         token_index += 1;
     }
 
+    // Text that doesn't end in a newline still has its last line.
+    if !generator.cell_line.is_empty() {
+        generator.cell_buffer.push(generator.cell_line);
+        generator.wrapped.push(false);
+    }
+
     //print_cells(&generator.cell_buffer);
 
     CellPanel {
         cells: generator.cell_buffer,
+        wrapped: generator.wrapped,
         fitted_buffer: Vec::new(),
         index: 0,
         width,
@@ -156,11 +213,14 @@ pub fn render_markdown(mark_down_text: &String, width: usize, height: usize) ->
 #[derive(Default)]
 struct CellGenerator {
     cell_buffer: Vec<Vec<Cell>>,
+    wrapped: Vec<bool>,
     cell_line: Vec<Cell>,
     bold: bool,
     italic: bool,
     header: bool,
     line_width: usize,
+    /// Most rows an image may take, so it fits the panel.
+    image_height: usize,
 }
 
 impl CellGenerator {
@@ -191,12 +251,38 @@ impl CellGenerator {
 
         if self.cell_line.len() >= self.line_width {
             self.cell_buffer.push(self.cell_line.clone());
+            self.wrapped.push(true);
             self.cell_line = Vec::new();
         }
     }
 }
 
-fn has_token(token_index: usize, tokens: &Vec<Token>, target: Token) -> bool { 
+impl CellGenerator {
+    /// Draws an image as art as wide as the line allows. Its last row is left
+    /// as the current line for the newline after it to finish.
+    fn gen_image(&mut self, alt: &str, image: Option<&Image>, style: ArtStyle) {
+        let Some(image) = image else {
+            format!("[{}]", alt).chars().for_each(|c| self.gen_cell(c));
+            return;
+        };
+
+        let (columns, rows) = fit_size(image, self.line_width, self.image_height.max(1));
+        for art_line in image_to_art(image, columns, rows, style) {
+            if !self.cell_line.is_empty() {
+                self.cell_buffer.push(std::mem::take(&mut self.cell_line));
+                self.wrapped.push(false);
+            }
+            self.cell_line = art_line.into_iter().map(|art_cell| Cell {
+                char: art_cell.char,
+                font_type: FontType::ResumeDefault,
+                foreground_color: art_cell.foreground.unwrap_or(&GREEN),
+                background_color: art_cell.background,
+            }).collect();
+        }
+    }
+}
+
+fn has_token(token_index: usize, tokens: &[Token], target: Token) -> bool { 
     let mut new_index = token_index;
     while new_index < tokens.len() && tokens[new_index] != Token::NewLine {
         if tokens[new_index] == target {
@@ -205,5 +291,41 @@ fn has_token(token_index: usize, tokens: &Vec<Token>, target: Token) -> bool {
         new_index += 1;
 
     }
-    return false;
+    false
+}
+
+/// Markdown the renderer doesn't understand and would show as typed, as
+/// one-based line numbers with what was found there.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn unsupported_constructs(mark_down_text: &str) -> Vec<(usize, &'static str)> {
+    let mut found = Vec::new();
+    for (line_index, line) in mark_down_text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let construct = if trimmed.starts_with("##") {
+            Some("only one level of heading is supported")
+        }
+        else if trimmed.contains('`') {
+            Some("code spans and code blocks are not supported")
+        }
+        else if trimmed.contains("![") && image_line(trimmed).is_none() {
+            Some("images need a line of their own, as `![alt](path)` or `![alt](path \"color\")`")
+        }
+        else if trimmed.contains("](") && image_line(trimmed).is_none() {
+            Some("links are not supported")
+        }
+        else if trimmed.starts_with('|') {
+            Some("tables are not supported")
+        }
+        else if trimmed.starts_with('<') {
+            Some("HTML is not supported")
+        }
+        else {
+            None
+        };
+
+        if let Some(construct) = construct {
+            found.push((line_index + 1, construct));
+        }
+    }
+    found
 }
@@ -1,30 +1,154 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use macroquad::prelude::*;
 
+use crate::{assets::AssetRegistry, background_loading::BackgroundLoader, overlay::OverlayLayer, palette::CommandPalette, render_cache::RenderCache, selection::Selection};
+
+/// Whether a project is still being worked on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectStatus {
+    Active,
+    Archived,
+}
+
+/// A named link on a project, like its repository or a demo.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProjectLink {
+    pub name: String,
+    pub url: String,
+}
+
+/// A project, from its `projects.json` entry and the front matter of its
+/// markdown. Everything after `markdown` is optional, so older project lists
+/// still load.
 #[derive(Deserialize)]
 pub struct ProjectInfo {
     pub name: String,
-    pub ascii_art: Vec<String>,
+    /// Frames of art, shown one after another while the project is selected.
+    #[serde(default, deserialize_with = "art_frames")]
+    pub ascii_art: Vec<Vec<String>>,
+    /// Colour masks laid over `ascii_art`, one palette key character per art
+    /// character. Spaces and dots keep the default colours. A single frame
+    /// colours every frame of the art.
+    #[serde(default, deserialize_with = "art_frames")]
+    pub ascii_art_colors: Vec<Vec<String>>,
+    #[serde(default, deserialize_with = "art_frames")]
+    pub ascii_art_backgrounds: Vec<Vec<String>>,
+    /// Seconds each frame of `ascii_art` is shown, unless `frame_delays` has its own.
+    pub frame_duration: Option<f32>,
+    #[serde(default)]
+    pub frame_delays: Vec<f32>,
     pub markdown: String,
-    pub url: String,
+    /// Projects are listed by `order`, then in the order they were found.
+    pub order: Option<i64>,
+    /// The project's page on the blog, relative to the projects section unless it is a full URL.
+    pub url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Dates are shown as written, e.g. `2023-04`.
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub status: Option<ProjectStatus>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub links: Vec<ProjectLink>,
+}
+
+/// Art is written as the lines of a single frame or as a list of frames.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ArtFrames {
+    Frame(Vec<String>),
+    Frames(Vec<Vec<String>>),
+}
+
+fn art_frames<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<String>>, D::Error> {
+    Ok(match ArtFrames::deserialize(deserializer)? {
+        ArtFrames::Frame(lines) if lines.is_empty() => Vec::new(),
+        ArtFrames::Frame(lines) => vec![lines],
+        ArtFrames::Frames(frames) => frames,
+    })
+}
+
+/// Where the selected project's art is in its animation.
+#[derive(Default)]
+pub struct ArtAnimation {
+    /// The project the animation belongs to, so selecting another starts it over.
+    pub project_index: Option<usize>,
+    pub frame: usize,
+    pub frame_time: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BorderStyle {
+    Light,
+    #[default]
+    Heavy,
+    Double,
+    Rounded,
+    Ascii,
 }
 
-#[derive(Debug, Default)]
+impl BorderStyle {
+    pub const ALL: [BorderStyle; 5] = [BorderStyle::Light, BorderStyle::Heavy, BorderStyle::Double, BorderStyle::Rounded, BorderStyle::Ascii];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BorderStyle::Light => "light",
+            BorderStyle::Heavy => "heavy",
+            BorderStyle::Double => "double",
+            BorderStyle::Rounded => "rounded",
+            BorderStyle::Ascii => "ascii",
+        }
+    }
+
+    /// Style of the focused panel when screen borders use this style. Only
+    /// light borders change, the others show focus by colour alone.
+    pub fn focused(self) -> BorderStyle {
+        match self {
+            BorderStyle::Light => BorderStyle::Heavy,
+            style => style,
+        }
+    }
+
+    /// Style of a panel drawn over the screen that wants `style`, which it
+    /// keeps unless screen borders are ASCII only.
+    pub fn overlay(self, style: BorderStyle) -> BorderStyle {
+        match self {
+            BorderStyle::Ascii => BorderStyle::Ascii,
+            _ => style,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct CellPanel {
     pub cells: Vec<Vec<Cell>>,
+    /// Marks the lines of `cells` that were soft wrapped and continue on the next line.
+    pub wrapped: Vec<bool>,
     pub fitted_buffer: Vec<Vec<Cell>>,
+    /// Same as `wrapped`, for the lines of `fitted_buffer`.
+    pub fitted_wrapped: Vec<bool>,
     pub box_color: Option<&'static Color>,
+    pub border_style: BorderStyle,
+    pub title: Option<String>,
+    pub focused: bool,
     pub index: usize,
+    /// Line shown at the top while smooth scrolling catches up with `index`.
+    pub scroll_position: f32,
     pub width: usize,
     pub height: usize,
     pub offset_x: usize,
     pub offset_y: usize,
+    /// Set on panels that came out of `RenderCache`, to tell renders apart.
+    pub render_id: Option<u64>,
 }
 
 impl CellPanel {
-    pub fn from_strings(value: &Vec<String>, width: usize, height: usize, offset_x: usize, offset_y: usize) -> Self {
+    pub fn from_strings(value: &[String], width: usize, height: usize, offset_x: usize, offset_y: usize) -> Self {
         let cells: Vec<Vec<Cell>> = value.iter().map(|line| {
             let cell_line: Vec<Cell> = line.chars().map(|c| {
                 Cell {
@@ -48,10 +172,15 @@ impl CellPanel {
         };
 
         cell_panel.fit_buffer();
-        return cell_panel;
+        cell_panel
     }
 
-    pub fn update_from_strings(&mut self, strings: &Vec<String>) {
+    pub fn update_from_cells(&mut self, cells: Vec<Vec<Cell>>) {
+        self.cells = cells;
+        self.fit_buffer();
+    }
+
+    pub fn update_from_strings(&mut self, strings: &[String]) {
         let cells: Vec<Vec<Cell>> = strings.iter().map(|line| {
             let cell_line: Vec<Cell> = line.chars().map(|c| {
                 Cell {
@@ -73,14 +202,22 @@ impl CellPanel {
 impl CellPanel {
     pub fn fit_buffer(&mut self) {
         self.fitted_buffer = Vec::new();
+        self.fitted_wrapped = Vec::new();
+
+        if self.width == 0 {
+            self.fitted_buffer = vec![Vec::new(); self.height];
+            self.fitted_wrapped = vec![false; self.height];
+            return;
+        }
 
         let mut col = 0;
         let mut current_cell_line : Vec<Cell> = vec![Cell::default(); self.width];
-        for cell_line in self.cells.iter() {
+        for (line_index, cell_line) in self.cells.iter().enumerate() {
 
             for cell in cell_line.iter() {
                 if col >= self.width {
                     self.fitted_buffer.push(current_cell_line);
+                    self.fitted_wrapped.push(true);
                     current_cell_line = vec![Cell::default(); self.width];
                     col = 0;
                 }
@@ -89,6 +226,7 @@ impl CellPanel {
             }
 
             self.fitted_buffer.push(current_cell_line);
+            self.fitted_wrapped.push(self.wrapped.get(line_index).copied().unwrap_or(false));
             current_cell_line = vec![Cell::default(); self.width];
             col = 0;
         }
@@ -96,18 +234,36 @@ impl CellPanel {
         let current_height = self.fitted_buffer.len();
         for _ in current_height..self.height  {
             self.fitted_buffer.push(vec![Cell::default(); self.width]);
+            self.fitted_wrapped.push(false);
         }
     }
-    pub fn write_to_buffer(&self, char_buffer: &mut Vec<Vec<Cell>>) {
-        let mut row = self.offset_y;
+    /// Largest `index` that still fills the panel.
+    pub fn max_index(&self) -> usize {
+        self.fitted_buffer.len().saturating_sub(self.height)
+    }
 
-        for cell_line in self.fitted_buffer[usize::min(self.index, self.fitted_buffer.len() - 1)..(usize::min(self.index + self.height, self.fitted_buffer.len()))].iter() {
-            let mut col = self.offset_x;
-            for cell in cell_line.iter() {
-                char_buffer[row][col] = cell.clone();
-                col += 1;
+    /// Moves `index` by `lines`, staying within the content.
+    pub fn scroll_by(&mut self, lines: isize) {
+        self.index = usize::min(self.index.saturating_add_signed(lines), self.max_index());
+    }
+
+    pub fn is_scrolling(&self) -> bool {
+        self.scroll_position != self.index as f32
+    }
+
+    /// Copies the visible lines into `char_buffer`, clipping anything that
+    /// falls outside of it.
+    pub fn write_to_buffer(&self, char_buffer: &mut [Vec<Cell>]) {
+        let start = usize::min(self.index, self.fitted_buffer.len().saturating_sub(1));
+        let end = usize::min(self.index + self.height, self.fitted_buffer.len());
+        let visible_lines = self.fitted_buffer.get(start..end).unwrap_or_default();
+
+        let rows = char_buffer.iter_mut().skip(self.offset_y);
+        for (buffer_line, cell_line) in rows.zip(visible_lines) {
+            let cols = buffer_line.iter_mut().skip(self.offset_x);
+            for (buffer_cell, cell) in cols.zip(cell_line) {
+                *buffer_cell = cell.clone();
             }
-            row += 1;
         }
     }
 }
@@ -116,23 +272,53 @@ pub struct TerminalState {
     pub cell_buffer: Vec<Vec<Cell>>,
     pub line_buffer: Vec<String>,
     pub highlighted_boxes: Vec<Rect>,
+    pub hover_rect: Option<Rect>,
+    pub selection: Option<Selection>,
+    pub help_visible: bool,
+    pub palette: Option<CommandPalette>,
+    /// Index into `themes::THEMES`.
+    pub theme: usize,
+    /// Style of the borders between the panels of a screen.
+    pub border_style: BorderStyle,
+    pub overlay: OverlayLayer,
+    /// Drawn instead of `cell_buffer` while something is shown on top of the screen.
+    pub overlay_buffer: Option<Vec<Vec<Cell>>>,
     pub projects: Vec<ProjectInfo>,
-    pub loaded_projects: HashMap<String, String>,
+    /// Entries of `projects.json` as written, merged with front matter to make `projects`.
+    pub project_entries: Vec<serde_json::Value>,
+    /// Project markdown, loaded in the background as projects are selected.
+    pub project_files: BackgroundLoader,
+    /// Images shown in project markdown by content path, decoded once they load.
+    pub project_images: HashMap<String, Image>,
+    pub art_animation: ArtAnimation,
+    pub render_cache: RenderCache,
+    /// Files that failed to load and were skipped, so they aren't tried again.
+    pub skipped_files: HashSet<String>,
+    pub resume_markdown: Option<String>,
     pub line_index: usize,
     pub cursor_x: f32,
     pub cursor_y: f32,
     pub font_size: f32,
+    /// Pinch zoom applied on top of the font size fitted to the window.
+    pub font_scale: f32,
+    pub term_width: usize,
+    pub term_height: usize,
     pub terminal_width_px: f32,
     pub terminal_height_px: f32,
     pub sub_state: TermSubState,
-    pub default_font: Option<Font>,
-    pub resume_normal_font: Option<Font>,
-    pub resume_bold_font: Option<Font>,
-    pub resume_italic_font: Option<Font>,
-    pub resume_italic_bold_font: Option<Font>,
+    pub assets: AssetRegistry,
+}
+
+impl TerminalState {
+    /// Whether help, the command palette or a dialog is taking all input.
+    pub fn is_modal_open(&self) -> bool {
+        self.help_visible || self.palette.is_some() || self.overlay.active_dialog().is_some()
+    }
 }
 
+#[derive(Default)]
 pub enum TermSubState {
+    #[default]
     Load,
     Main {
         index: usize
@@ -141,6 +327,8 @@ pub enum TermSubState {
         selected_project_index: usize,
         project_about_scroll: usize,
         main_focus: bool,
+        /// Query typed to find a project by name, while the search is open.
+        search: Option<String>,
         cell_panels: Vec<CellPanel>,
     },
     Resume (CellPanel),
@@ -149,12 +337,28 @@ pub enum TermSubState {
     }
 }
 
-impl Default for TermSubState {
-    fn default() -> Self {
-        return TermSubState::Load;
+impl TermSubState {
+    /// Panels making up the current screen, in drawing order.
+    pub fn panels(&self) -> &[CellPanel] {
+        match self {
+            TermSubState::Projects { cell_panels, .. } => cell_panels,
+            TermSubState::Resume(panel) => std::slice::from_ref(panel),
+            _ => &[],
+        }
+    }
+
+    pub fn panels_mut(&mut self) -> &mut [CellPanel] {
+        match self {
+            TermSubState::Projects { cell_panels, .. } => cell_panels,
+            TermSubState::Resume(panel) => std::slice::from_mut(panel),
+            _ => &mut [],
+        }
     }
-}
 
+    pub fn focused_panel(&self) -> Option<&CellPanel> {
+        self.panels().iter().find(|panel| panel.focused)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Cell {
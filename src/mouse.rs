@@ -0,0 +1,115 @@
+use macroquad::{miniquad::window::dpi_scale, prelude::*};
+
+use crate::{
+    grid::cell_at_position,
+    main_item_box,
+    models::{TermSubState, TerminalState},
+    overlay,
+    projects::{ABOUT_PANEL_INDEX, PROJECTS_PANEL_INDEX},
+    selection::Selection,
+    ui::is_over_controls,
+};
+
+/// Something on the grid that reacts to the mouse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitTarget {
+    MainItem(usize),
+    Project(usize),
+    AboutPanel,
+    DialogButton(usize),
+}
+
+#[derive(Default)]
+pub struct MouseContext {
+    pub clicked: Option<HitTarget>,
+    press_cell: Option<(usize, usize)>,
+    dragging: bool,
+}
+
+impl MouseContext {
+    pub fn reset(&mut self) {
+        self.clicked = None;
+    }
+}
+
+/// Finds what is under `cell`, along with the cells to highlight while hovering it.
+pub fn hit_target(terminal_state: &TerminalState, cell: (usize, usize)) -> Option<(HitTarget, Option<Rect>)> {
+    let point = vec2(cell.0 as f32, cell.1 as f32);
+
+    // An open dialog traps the pointer, so only its buttons can be hit.
+    if let Some(dialog) = terminal_state.overlay.active_dialog() {
+        let (_, button_rects) = overlay::dialog_layout(terminal_state, dialog);
+        return button_rects.into_iter().enumerate()
+            .find(|(_, rect)| rect.contains(point))
+            .map(|(button, rect)| (HitTarget::DialogButton(button), Some(rect)));
+    }
+
+    match &terminal_state.sub_state {
+        TermSubState::Main { .. } => (0..3).find_map(|index| {
+            let item_box = main_item_box(terminal_state, index)?;
+            item_box.contains(point).then_some((HitTarget::MainItem(index), Some(item_box)))
+        }),
+        TermSubState::Projects { cell_panels, .. } => {
+            let projects_panel = &cell_panels[PROJECTS_PANEL_INDEX];
+            let about_panel = &cell_panels[ABOUT_PANEL_INDEX];
+
+            if panel_rect(projects_panel.offset_x, projects_panel.offset_y, projects_panel.width, projects_panel.height).contains(point) {
+                let project_index = projects_panel.index + cell.1 - projects_panel.offset_y;
+                let row = Rect::new(projects_panel.offset_x as f32, cell.1 as f32, projects_panel.width as f32, 1f32);
+                (project_index < terminal_state.projects.len()).then_some((HitTarget::Project(project_index), Some(row)))
+            }
+            else if panel_rect(about_panel.offset_x, about_panel.offset_y, about_panel.width, about_panel.height).contains(point) {
+                Some((HitTarget::AboutPanel, None))
+            }
+            else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn panel_rect(offset_x: usize, offset_y: usize, width: usize, height: usize) -> Rect {
+    Rect::new(offset_x as f32, offset_y as f32, width as f32, height as f32)
+}
+
+/// Tracks hovering, clicks and drag selection for the frame. A press and
+/// release on the same cell is a click, moving to another cell while held
+/// selects the cells in between. Touches also move the mouse, so everything
+/// is left to the touch handling while the screen is being touched.
+pub fn handle_mouse(screen_w: f32, screen_h: f32, touch_in_use: bool, mouse_context: &mut MouseContext, terminal_state: &mut TerminalState) {
+    let (mouse_x, mouse_y) = mouse_position();
+    if touch_in_use || is_over_controls(screen_w, screen_h, vec2(mouse_x, mouse_y), true, terminal_state) {
+        mouse_context.press_cell = None;
+        mouse_context.dragging = false;
+        terminal_state.hover_rect = None;
+        return;
+    }
+
+    let cell = cell_at_position(terminal_state, mouse_x * dpi_scale(), mouse_y * dpi_scale());
+    let target = cell.and_then(|cell| hit_target(terminal_state, cell));
+    terminal_state.hover_rect = target.and_then(|(_, rect)| rect);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        mouse_context.press_cell = cell;
+        mouse_context.dragging = false;
+        terminal_state.selection = None;
+    }
+
+    if is_mouse_button_down(MouseButton::Left) && !terminal_state.is_modal_open() {
+        if let (Some(press_cell), Some(cell)) = (mouse_context.press_cell, cell) {
+            if mouse_context.dragging || cell != press_cell {
+                mouse_context.dragging = true;
+                terminal_state.selection = Some(Selection { anchor: press_cell, head: cell });
+            }
+        }
+    }
+
+    if is_mouse_button_released(MouseButton::Left) {
+        if !mouse_context.dragging && cell.is_some() && cell == mouse_context.press_cell {
+            mouse_context.clicked = target.map(|(target, _)| target);
+        }
+        mouse_context.press_cell = None;
+        mouse_context.dragging = false;
+    }
+}
@@ -4,6 +4,7 @@ pub fn open_url(url: &str) {
     use opener::open;
     open(url).unwrap();
 }
+#[cfg(target_arch="wasm32")]
 extern "C" {
     fn open_new_tab(ptr: *const i8, len: u32);
 }
@@ -0,0 +1,272 @@
+use macroquad::prelude::*;
+
+use crate::{
+    error::Error,
+    help,
+    input::Bindings,
+    models::{BorderStyle, Cell, CellPanel, TerminalState},
+    palette,
+    utils::write_overlay_panels,
+};
+
+/// Seconds dialogs and toasts take to open and to close.
+const TRANSITION_TIME: f32 = 0.15;
+/// Seconds a toast stays up before it leaves on its own.
+const TOAST_TIME: f32 = 2.5;
+const DIALOG_MAX_WIDTH: usize = 44;
+const BUTTON_GAP: usize = 2;
+
+/// What a dialog was opened for, so its answer can be acted on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DialogKind {
+    Contact,
+    /// A file failed to load. Answered with `RETRY_BUTTON` or skip.
+    LoadError { path: String },
+    /// Picking which of a project's links to open. Every button but the last opens the link at its index.
+    ProjectLinks { project_index: usize },
+}
+
+pub const RETRY_BUTTON: usize = 0;
+
+pub struct Dialog {
+    pub kind: DialogKind,
+    pub title: String,
+    pub message: String,
+    pub buttons: Vec<String>,
+    pub focused_button: usize,
+    /// Button that answers the dialog when it is dismissed with Back.
+    pub cancel_button: usize,
+    age: f32,
+    /// Time since the dialog was answered and started closing.
+    leaving: Option<f32>,
+}
+
+impl Dialog {
+    pub fn new(kind: DialogKind, title: &str, message: &str, buttons: &[&str]) -> Self {
+        Dialog {
+            kind,
+            title: title.to_string(),
+            message: message.to_string(),
+            buttons: buttons.iter().map(|button| button.to_string()).collect(),
+            focused_button: 0,
+            cancel_button: buttons.len().saturating_sub(1),
+            age: 0f32,
+            leaving: None,
+        }
+    }
+}
+
+struct Toast {
+    message: String,
+    age: f32,
+}
+
+/// How far through its transition something is, from 0 when hidden to 1 when fully shown.
+fn visibility(age: f32, leaving: Option<f32>) -> f32 {
+    match leaving {
+        Some(leaving) => 1f32 - (leaving / TRANSITION_TIME).min(1f32),
+        None => (age / TRANSITION_TIME).min(1f32),
+    }
+}
+
+/// Dialogs and toasts drawn on top of the current screen.
+#[derive(Default)]
+pub struct OverlayLayer {
+    dialogs: Vec<Dialog>,
+    toasts: Vec<Toast>,
+}
+
+impl OverlayLayer {
+    pub fn show_dialog(&mut self, dialog: Dialog) {
+        self.dialogs.push(dialog);
+    }
+
+    pub fn toast(&mut self, message: &str) {
+        self.toasts.push(Toast { message: message.to_string(), age: 0f32 });
+    }
+
+    /// Asks whether to retry or skip a file that failed to load, unless that is already being asked.
+    pub fn show_load_error(&mut self, error: &Error) {
+        error!("{} ({:?})", error, error);
+        let kind = DialogKind::LoadError { path: error.path().to_string() };
+        if self.active_dialog().is_some_and(|dialog| dialog.kind == kind) {
+            return;
+        }
+        self.show_dialog(Dialog::new(kind, "error", &format!("{}.", error), &["Retry", "Skip"]));
+    }
+
+    /// Topmost dialog still waiting for an answer. It gets all input until it is answered.
+    pub fn active_dialog(&self) -> Option<&Dialog> {
+        self.dialogs.iter().rev().find(|dialog| dialog.leaving.is_none())
+    }
+
+    /// Moves the focus between the buttons of the active dialog, wrapping around.
+    pub fn move_dialog_focus(&mut self, step: isize) {
+        if let Some(dialog) = self.dialogs.iter_mut().rev().find(|dialog| dialog.leaving.is_none()) {
+            let button_count = dialog.buttons.len() as isize;
+            dialog.focused_button = (dialog.focused_button as isize + step).rem_euclid(button_count.max(1)) as usize;
+        }
+    }
+
+    /// Answers the active dialog with `button` and starts closing it.
+    pub fn answer_dialog(&mut self, button: usize) -> Option<(DialogKind, usize)> {
+        let dialog = self.dialogs.iter_mut().rev().find(|dialog| dialog.leaving.is_none())?;
+        dialog.leaving = Some(0f32);
+        Some((dialog.kind.clone(), button))
+    }
+
+    /// Answers the active dialog with the clicked button, the focused one on
+    /// `confirm` or its cancel button on `cancel`, otherwise moves the focus by `focus_step`.
+    pub fn handle_dialog_input(&mut self, clicked_button: Option<usize>, confirm: bool, cancel: bool, focus_step: isize) -> Option<(DialogKind, usize)> {
+        let dialog = self.active_dialog()?;
+        let answer = match clicked_button {
+            Some(button) => Some(button),
+            None if confirm => Some(dialog.focused_button),
+            None if cancel => Some(dialog.cancel_button),
+            None => None,
+        };
+        match answer {
+            Some(button) => self.answer_dialog(button),
+            None => {
+                self.move_dialog_focus(focus_step);
+                None
+            }
+        }
+    }
+
+    pub fn update(&mut self, frame_time: f32) {
+        for dialog in self.dialogs.iter_mut() {
+            dialog.age += frame_time;
+            if let Some(leaving) = dialog.leaving.as_mut() {
+                *leaving += frame_time;
+            }
+        }
+        self.dialogs.retain(|dialog| dialog.leaving.is_none_or(|leaving| leaving < TRANSITION_TIME));
+
+        for toast in self.toasts.iter_mut() {
+            toast.age += frame_time;
+        }
+        self.toasts.retain(|toast| toast.age < TOAST_TIME + TRANSITION_TIME);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dialogs.is_empty() && self.toasts.is_empty()
+    }
+}
+
+/// Splits `text` into lines of at most `width` characters, breaking between words when possible.
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+/// The dialog's panel, rolled open as far as its transition allows, and the
+/// cells covered by each of its buttons.
+pub fn dialog_layout(terminal_state: &TerminalState, dialog: &Dialog) -> (CellPanel, Vec<Rect>) {
+    let buffer_height = terminal_state.cell_buffer.len();
+    let buffer_width = terminal_state.cell_buffer.first().map_or(0, |line| line.len());
+
+    let labels: Vec<String> = dialog.buttons.iter().map(|button| format!("[ {} ]", button)).collect();
+    let buttons_width = labels.iter().map(|label| label.chars().count()).sum::<usize>() + BUTTON_GAP * labels.len().saturating_sub(1);
+    let max_width = buffer_width.saturating_sub(4);
+    let mut lines = wrap_words(&dialog.message, DIALOG_MAX_WIDTH.min(max_width).saturating_sub(2));
+    let text_width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) + 2;
+    let width = text_width.max(buttons_width + 2).max(dialog.title.chars().count() + 4).min(max_width);
+
+    for line in lines.iter_mut() {
+        line.insert(0, ' ');
+    }
+    lines.push(String::new());
+    let buttons_row = lines.len();
+    let buttons_x = width.saturating_sub(buttons_width) / 2;
+    lines.push(format!("{}{}", " ".repeat(buttons_x), labels.join(&" ".repeat(BUTTON_GAP))));
+
+    let full_height = lines.len().min(buffer_height.saturating_sub(4));
+    let height = ((full_height as f32 * visibility(dialog.age, dialog.leaving)).ceil() as usize).clamp(1, full_height.max(1));
+    let offset_x = buffer_width.saturating_sub(width) / 2;
+    let offset_y = buffer_height.saturating_sub(full_height) / 2;
+
+    let mut panel = CellPanel::from_strings(&lines, width, height, offset_x, offset_y);
+    let mut button_rects = Vec::new();
+    let mut button_x = buttons_x;
+    for (button_index, label) in labels.iter().enumerate() {
+        let label_width = label.chars().count();
+        if button_index == dialog.focused_button {
+            panel.cells[buttons_row].iter_mut().skip(button_x).take(label_width)
+                .for_each(|cell| cell.background_color = Some(&WHITE));
+        }
+        if buttons_row < height {
+            button_rects.push(Rect::new((offset_x + button_x) as f32, (offset_y + buttons_row) as f32, label_width as f32, 1f32));
+        }
+        button_x += label_width + BUTTON_GAP;
+    }
+    panel.fit_buffer();
+    panel.title = Some(dialog.title.clone());
+    panel.focused = true;
+    panel.border_style = terminal_state.border_style.overlay(BorderStyle::Double);
+
+    (panel, button_rects)
+}
+
+/// Toasts sit in the bottom right corner, newest at the bottom. They type
+/// themselves in and drop off the bottom of the screen when they leave.
+fn toast_panels(terminal_state: &TerminalState) -> Vec<CellPanel> {
+    let buffer_height = terminal_state.cell_buffer.len();
+    let buffer_width = terminal_state.cell_buffer.first().map_or(0, |line| line.len());
+
+    terminal_state.overlay.toasts.iter().rev().enumerate().map(|(stack_index, toast)| {
+        let leaving = (toast.age - TOAST_TIME).max(0f32);
+        let shown = visibility(toast.age, (toast.age > TOAST_TIME).then_some(leaving));
+        let message: String = match toast.age > TOAST_TIME {
+            true => toast.message.clone(),
+            false => toast.message.chars().take((toast.message.chars().count() as f32 * shown).ceil() as usize).collect(),
+        };
+
+        let width = toast.message.chars().count().min(buffer_width.saturating_sub(4));
+        let drop = match toast.age > TOAST_TIME {
+            true => ((1f32 - shown) * 3f32).ceil() as usize,
+            false => 0,
+        };
+        let offset_x = buffer_width.saturating_sub(width + 2);
+        let offset_y = buffer_height.saturating_sub(2 + stack_index * 3) + drop;
+
+        let mut panel = CellPanel::from_strings(&[message], width, 1, offset_x, offset_y);
+        panel.border_style = terminal_state.border_style.overlay(BorderStyle::Rounded);
+        panel
+    }).collect()
+}
+
+/// The current screen with help, the command palette, dialogs and toasts
+/// drawn over it, or None when there is nothing to draw on top.
+pub fn compose(terminal_state: &TerminalState, bindings: &Bindings) -> Option<Vec<Vec<Cell>>> {
+    let mut panels = Vec::new();
+    if let Some(palette) = &terminal_state.palette {
+        panels.push(palette::palette_panel(terminal_state, palette));
+    }
+    if terminal_state.help_visible {
+        panels.push(help::help_panel(terminal_state, bindings));
+    }
+    panels.extend(terminal_state.overlay.dialogs.iter().map(|dialog| dialog_layout(terminal_state, dialog).0));
+    panels.extend(toast_panels(terminal_state));
+
+    if panels.is_empty() {
+        return None;
+    }
+
+    let mut cell_buffer = terminal_state.cell_buffer.clone();
+    for panel in &panels {
+        write_overlay_panels(std::slice::from_ref(panel), &mut cell_buffer);
+    }
+    Some(cell_buffer)
+}
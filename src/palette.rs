@@ -0,0 +1,216 @@
+use macroquad::prelude::*;
+
+use crate::{
+    error::Result,
+    input::{Action, InputContext},
+    models::{BorderStyle, CellPanel, TermSubState, TerminalState},
+    projects::{setup_projects, update_project_buffer},
+    relayout,
+    resume::setup_resume,
+    setup_main_state,
+    show_contact_dialog,
+    themes::THEMES,
+};
+
+const PALETTE_WIDTH: usize = 40;
+const PALETTE_MAX_ENTRIES: usize = 10;
+
+/// Somewhere the command palette can take the visitor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Destination {
+    Home,
+    Project(usize),
+    Resume,
+    Contact,
+    Theme(usize),
+    Borders(BorderStyle),
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    pub query: String,
+    /// Position of the highlighted entry in the filtered list.
+    pub selected: usize,
+}
+
+fn destinations(terminal_state: &TerminalState) -> Vec<(Destination, String)> {
+    let projects = terminal_state.projects.iter().enumerate()
+        .map(|(index, project)| (Destination::Project(index), format!("project: {}", project.name)));
+    let themes = THEMES.iter().enumerate()
+        .map(|(index, theme)| (Destination::Theme(index), format!("theme: {}", theme.name)));
+    let border_styles = BorderStyle::ALL.into_iter()
+        .map(|style| (Destination::Borders(style), format!("borders: {}", style.name())));
+
+    std::iter::once((Destination::Home, String::from("home")))
+        .chain(projects)
+        .chain([
+            (Destination::Resume, String::from("resume")),
+            (Destination::Contact, String::from("contact")),
+        ])
+        .chain(themes)
+        .chain(border_styles)
+        .collect()
+}
+
+/// Scores `label` against `query` when every character of the query appears in
+/// it in order, ignoring case. Runs of consecutive characters and matches near
+/// the start of the label score higher.
+pub fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let mut label_chars = label.chars().flat_map(char::to_lowercase).enumerate();
+    let mut score = 0;
+    let mut previous_position: Option<usize> = None;
+
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        let (position, _) = label_chars.find(|(_, label_char)| *label_char == query_char)?;
+        score += match previous_position {
+            Some(previous) if previous + 1 == position => 3,
+            Some(_) => 1,
+            None => 3 - position.min(3) as i32,
+        };
+        previous_position = Some(position);
+    }
+
+    Some(score)
+}
+
+/// Destinations matching the query, best match first.
+fn filtered_destinations(terminal_state: &TerminalState, query: &str) -> Vec<(Destination, String)> {
+    let mut scored: Vec<(i32, (Destination, String))> = destinations(terminal_state).into_iter()
+        .filter_map(|entry| fuzzy_score(query, &entry.1).map(|score| (score, entry)))
+        .collect();
+    scored.sort_by_key(|(score, _)| -score);
+    scored.into_iter().map(|(_, entry)| entry).collect()
+}
+
+pub fn palette_panel(terminal_state: &TerminalState, palette: &CommandPalette) -> CellPanel {
+    let entries = filtered_destinations(terminal_state, &palette.query);
+    let buffer_height = terminal_state.cell_buffer.len();
+    let buffer_width = terminal_state.cell_buffer.first().map_or(0, |line| line.len());
+
+    let mut lines = vec![format!("> {}", palette.query)];
+    lines.extend(entries.iter().map(|(_, label)| format!("  {}", label)));
+    if entries.is_empty() {
+        lines.push(String::from("  no matches"));
+    }
+
+    let width = PALETTE_WIDTH.min(buffer_width.saturating_sub(4));
+    let height = lines.len().min(PALETTE_MAX_ENTRIES + 1).min(buffer_height.saturating_sub(4));
+    let offset_x = (buffer_width - width) / 2;
+    let offset_y = (buffer_height - height) / 3;
+
+    let mut panel = CellPanel::from_strings(&lines, width, height, offset_x, offset_y);
+    if let Some(selected_line) = panel.cells.get_mut(palette.selected + 1).filter(|_| !entries.is_empty()) {
+        selected_line.iter_mut().for_each(|cell| cell.background_color = Some(&WHITE));
+        selected_line[0].char = '>';
+    }
+    panel.fit_buffer();
+    // Keep the highlighted entry in view below the query line.
+    panel.index = (palette.selected + 2).saturating_sub(height);
+    panel.title = Some(String::from("commands"));
+    panel.focused = true;
+    panel.border_style = terminal_state.border_style.overlay(BorderStyle::Double);
+    panel
+}
+
+/// Edits the query, moves the highlight and opens the chosen destination.
+/// Closing the palette or picking an entry hands input back to the screen.
+pub async fn handle_palette_input(terminal_state: &mut TerminalState, input_context: &InputContext) -> Result<()> {
+    let Some(mut palette) = terminal_state.palette.take() else {
+        return Ok(());
+    };
+
+    if input_context.is_pressed(Action::Back) || input_context.is_pressed(Action::Palette) {
+        return Ok(());
+    }
+
+    if !input_context.typed.is_empty() || input_context.is_pressed(Action::Erase) {
+        palette.query.extend(&input_context.typed);
+        if input_context.is_pressed(Action::Erase) {
+            palette.query.pop();
+        }
+        palette.selected = 0;
+    }
+
+    let entries = filtered_destinations(terminal_state, &palette.query);
+    if input_context.is_pressed(Action::Up) {
+        palette.selected = palette.selected.saturating_sub(1);
+    }
+    if input_context.is_pressed(Action::Down) {
+        palette.selected = usize::min(palette.selected + 1, entries.len().saturating_sub(1));
+    }
+
+    if input_context.is_pressed(Action::Select) {
+        if let Some((destination, _)) = entries.get(palette.selected) {
+            return go_to(terminal_state, *destination).await;
+        }
+    }
+
+    terminal_state.palette = Some(palette);
+    Ok(())
+}
+
+async fn go_to(terminal_state: &mut TerminalState, destination: Destination) -> Result<()> {
+    match destination {
+        Destination::Home => setup_main_state(terminal_state),
+        Destination::Project(project_index) => {
+            if !matches!(terminal_state.sub_state, TermSubState::Projects { .. }) {
+                setup_projects(terminal_state)?;
+            }
+            if let TermSubState::Projects { selected_project_index, main_focus, search, .. } = &mut terminal_state.sub_state {
+                *selected_project_index = project_index;
+                *main_focus = false;
+                *search = None;
+            }
+            update_project_buffer(terminal_state)?;
+        }
+        Destination::Resume => setup_resume(terminal_state).await?,
+        Destination::Contact => show_contact_dialog(terminal_state),
+        Destination::Theme(theme_index) => {
+            terminal_state.theme = theme_index;
+            terminal_state.overlay.toast(&format!("Theme: {}", THEMES[theme_index].name));
+        }
+        Destination::Borders(style) => {
+            terminal_state.border_style = style;
+            relayout(terminal_state).await?;
+            terminal_state.overlay.toast(&format!("Borders: {}", style.name()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_missing_a_character_or_out_of_order_do_not_match() {
+        assert_eq!(fuzzy_score("xyz", "resume"), None);
+        assert_eq!(fuzzy_score("ser", "resume"), None);
+        assert_eq!(fuzzy_score("resumes", "resume"), None);
+    }
+
+    #[test]
+    fn consecutive_characters_outrank_scattered_ones() {
+        assert!(fuzzy_score("rem", "remote") > fuzzy_score("rem", "resume"));
+        assert!(fuzzy_score("pong", "pong") > fuzzy_score("pong", "ping pong"));
+    }
+
+    #[test]
+    fn matches_near_the_start_rank_higher() {
+        assert!(fuzzy_score("con", "contact") > fuzzy_score("con", "reconnect"));
+        assert_eq!(fuzzy_score("t", "theme: amber"), Some(3));
+        assert_eq!(fuzzy_score("m", "theme: amber"), Some(0));
+    }
+
+    #[test]
+    fn matching_ignores_case() {
+        assert_eq!(fuzzy_score("RES", "resume"), Some(9));
+        assert_eq!(fuzzy_score("res", "Resume"), Some(9));
+    }
+
+    #[test]
+    fn an_empty_query_matches_everything() {
+        assert_eq!(fuzzy_score("", "resume"), Some(0));
+        assert_eq!(fuzzy_score("", ""), Some(0));
+    }
+}
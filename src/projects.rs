@@ -1,114 +1,468 @@
+use std::collections::HashMap;
+
 use macroquad::prelude::*;
 use crate::models::Cell;
+use serde_json::Value;
 use crate::{
-    markdown_renderer::render_markdown, models::{CellPanel, ProjectInfo, TermSubState, TerminalState}, 
-    utils::write_cell_panels_with_border
+    ascii_image::decode_image,
+    background_loading::BackgroundLoader,
+    content,
+    error::{Error, Result},
+    front_matter::{entry_markdown, merge_project},
+    layout::{Constraint, Layout},
+    markdown_renderer::image_paths,
+    models::{ArtAnimation, CellPanel, FontType, ProjectInfo, ProjectLink, ProjectStatus, TermSubState, TerminalState},
+    opener::open_url,
+    overlay::{Dialog, DialogKind},
+    themes::PaletteKey,
+    utils::write_cell_panels_with_border,
+    window_focus::window_visible
 };
 
-const PROJECT_SIDE_WIDTH: usize = 21;
-const PROJECT_ART_HEIGHT: usize = 8;
-const TERM_HEIGHT: usize = 20;
-const TERM_WIDTH: usize = 80;
+pub const PROJECT_SIDE_WIDTH: usize = 21;
+pub const PROJECT_ART_HEIGHT: usize = 8;
+/// Below this many columns the sidebar is stacked under the about panel
+/// instead of sitting beside it.
+const NARROW_TERM_WIDTH: usize = 60;
+/// Share of a wide terminal given to the sidebar once that is more than
+/// `PROJECT_SIDE_WIDTH`.
+const PROJECT_SIDE_PERCENT: usize = 25;
+/// One line of project details plus its border.
+const PROJECT_DETAILS_HEIGHT: usize = 3;
+/// Seconds each frame of project art is shown when the project doesn't say.
+const ART_FRAME_DURATION: f32 = 0.2;
+/// Where the relative `url` of a project points.
+const PROJECT_PAGE_BASE_URL: &str = "https://blog.cambucha.dev/projects/";
 
 pub const ABOUT_PANEL_INDEX: usize = 0;
 pub const ART_PANEL_INDEX: usize = 1;
 pub const PROJECTS_PANEL_INDEX: usize = 2;
+/// Only laid out and drawn when the selected project has details to show.
+pub const DETAILS_PANEL_INDEX: usize = 3;
 
+fn project_layout(term_width: usize, show_details: bool) -> Layout {
+    let mut main_column = vec![(Constraint::Fill, Layout::Panel(ABOUT_PANEL_INDEX))];
+    if show_details {
+        main_column.push((Constraint::Fixed(PROJECT_DETAILS_HEIGHT), Layout::Panel(DETAILS_PANEL_INDEX)));
+    }
 
-pub async fn setup_projects(terminal_state: &mut TerminalState) {
+    if term_width < NARROW_TERM_WIDTH {
+        main_column.push(
+            (Constraint::Fixed(PROJECT_ART_HEIGHT), Layout::horizontal(vec![
+                (Constraint::Fixed(PROJECT_SIDE_WIDTH), Layout::Panel(ART_PANEL_INDEX)),
+                (Constraint::Fill, Layout::Panel(PROJECTS_PANEL_INDEX)),
+            ])),
+        );
+        Layout::vertical(main_column)
+    }
+    else {
+        let side_width = if term_width * PROJECT_SIDE_PERCENT / 100 > PROJECT_SIDE_WIDTH {
+            Constraint::Percentage(PROJECT_SIDE_PERCENT)
+        }
+        else {
+            Constraint::Fixed(PROJECT_SIDE_WIDTH)
+        };
+        Layout::horizontal(vec![
+            (Constraint::Fill, Layout::vertical(main_column)),
+            (side_width, Layout::vertical(vec![
+                (Constraint::Fixed(PROJECT_ART_HEIGHT), Layout::Panel(ART_PANEL_INDEX)),
+                (Constraint::Fill, Layout::Panel(PROJECTS_PANEL_INDEX)),
+            ])),
+        ])
+    }
+}
 
-    let mut projects_str: Vec<String> = terminal_state.projects.iter().map(|project: &ProjectInfo| project.name.clone()).collect();
+/// Content directory holding each project's markdown.
+pub const PROJECTS_DIR: &str = "projects";
 
-    let project = &terminal_state.projects[0];
+/// Where a project's markdown is loaded from.
+pub fn project_markdown_path(project: &ProjectInfo) -> String {
+    format!("{}/{}", PROJECTS_DIR, project.markdown)
+}
+
+fn mask_key(mask: &[String], row: usize, column: usize) -> Option<PaletteKey> {
+    mask.get(row)?.chars().nth(column).and_then(PaletteKey::from_char)
+}
 
-    
-    if let None = terminal_state.loaded_projects.get(&project.markdown) {
-        let file_data = load_file(&format!("projects/{}", &project.markdown)).await.expect("Could not load markdown");
-        let text = String::from_utf8(file_data).expect("Could not parse markdown");
-        terminal_state.loaded_projects.insert(project.markdown.clone(), text);
+/// The mask for one frame of art, where a mask with a single frame covers every frame.
+pub fn mask_frame(mask: &[Vec<String>], frame: usize) -> &[String] {
+    let mask_frame = match mask.len() {
+        1 => mask.first(),
+        _ => mask.get(frame),
     };
+    mask_frame.map_or(&[], |lines| lines.as_slice())
+}
+
+/// One frame of a project's art as cells, coloured by its colour masks.
+pub fn art_cells(project: &ProjectInfo, frame: usize) -> Vec<Vec<Cell>> {
+    let (colors, backgrounds) = (mask_frame(&project.ascii_art_colors, frame), mask_frame(&project.ascii_art_backgrounds, frame));
+    let lines = project.ascii_art.get(frame).map_or(&[][..], |lines| lines.as_slice());
+    lines.iter().enumerate().map(|(row, line)| {
+        line.chars().enumerate().map(|(column, c)| Cell {
+            char: c,
+            foreground_color: mask_key(colors, row, column).map_or(&GREEN, PaletteKey::cell_color),
+            background_color: mask_key(backgrounds, row, column).map(PaletteKey::cell_color),
+            font_type: FontType::Default,
+        }).collect()
+    }).collect()
+}
 
-    let markdown = &terminal_state.loaded_projects[&project.markdown];
+/// Seconds a frame of a project's art stays up.
+fn frame_delay(project: &ProjectInfo, frame: usize) -> f32 {
+    project.frame_delays.get(frame).copied().or(project.frame_duration).unwrap_or(ART_FRAME_DURATION)
+}
 
-    let mut about_panel = render_markdown(markdown, 
-        TERM_WIDTH - PROJECT_SIDE_WIDTH - 1, 
-        TERM_HEIGHT - 2);
+/// Moves the selected project's art on to its next frame once the current one
+/// has been up long enough. The art holds its frame while the window is hidden
+/// or unfocused, and `dt` is capped at one frame's delay so a long stall steps
+/// one frame instead of racing to catch up.
+pub fn animate_project_art(terminal_state: &mut TerminalState, dt: f32) {
+    let (term_width, term_height) = (terminal_state.term_width, terminal_state.term_height);
+    let TermSubState::Projects { selected_project_index, ref mut cell_panels, .. } = terminal_state.sub_state else {
+        return;
+    };
+    let project = &terminal_state.projects[selected_project_index];
+    let animation = &mut terminal_state.art_animation;
+    if project.ascii_art.len() < 2 || animation.project_index != Some(selected_project_index) {
+        return;
+    }
 
-    about_panel.offset_x = 1;
-    about_panel.offset_y = 1;
+    if !window_visible() {
+        return;
+    }
+    let delay = frame_delay(project, animation.frame);
+    animation.frame_time += dt.min(delay);
+    if animation.frame_time < delay {
+        return;
+    }
+    animation.frame_time = 0f32;
+    animation.frame = (animation.frame + 1) % project.ascii_art.len();
 
-    about_panel.fit_buffer();
+    if let Some(art_panel) = cell_panels.get_mut(ART_PANEL_INDEX) {
+        art_panel.update_from_cells(art_cells(project, animation.frame));
+    }
+    terminal_state.cell_buffer = write_project_panels(cell_panels, term_width, term_height);
+}
+
+/// Draws the projects screen's panels, leaving out the details panel when it is empty.
+fn write_project_panels(cell_panels: &[CellPanel], term_width: usize, term_height: usize) -> Vec<Vec<Cell>> {
+    let shown_panels = match cell_panels[DETAILS_PANEL_INDEX].title {
+        Some(_) => cell_panels,
+        None => &cell_panels[..DETAILS_PANEL_INDEX],
+    };
+    write_cell_panels_with_border(shown_panels, term_width, term_height)
+}
 
-    let art_panel = CellPanel::from_strings(&project.ascii_art, 
-        PROJECT_SIDE_WIDTH - 2, 
-        PROJECT_ART_HEIGHT - 2, 
-        TERM_WIDTH - PROJECT_SIDE_WIDTH + 1, 1);
+/// Content path of an image in project markdown. Paths are relative to
+/// `projects/`, or to the content directory when they start with `/`.
+pub fn project_image_path(written_path: &str) -> String {
+    match written_path.strip_prefix('/') {
+        Some(path) => path.to_string(),
+        None => format!("{}/{}", PROJECTS_DIR, written_path.trim_start_matches("./")),
+    }
+}
+
+/// Starts loading an image for project markdown, decoding it once it has
+/// arrived. The decoded image is kept instead of its file.
+fn load_project_image(project_files: &mut BackgroundLoader, project_images: &mut HashMap<String, Image>, path: &str) -> Result<()> {
+    if project_images.contains_key(path) {
+        return Ok(());
+    }
+    project_files.request(path);
+    if let Some(error) = project_files.take_error(path) {
+        return Err(error);
+    }
+    let Some(bytes) = project_files.take(path) else {
+        return Ok(());
+    };
+    let image = decode_image(&bytes).map_err(|message| Error::Invalid { path: path.to_string(), message })?;
+    project_images.insert(path.to_string(), image);
+    Ok(())
+}
+
+/// A loaded file as text, or nothing while it loads or if it isn't UTF-8.
+fn loaded_text<'a>(project_files: &'a BackgroundLoader, path: &str) -> Option<&'a str> {
+    project_files.get(path).and_then(|bytes| std::str::from_utf8(bytes).ok())
+}
+
+/// Entries of a project list as written: markdown file names or project objects.
+pub fn parse_project_entries(path: &str, bytes: &[u8]) -> Result<Vec<Value>> {
+    let entries: Vec<Value> = serde_json::from_slice(bytes).map_err(|source| Error::Parse { path: path.to_string(), source })?;
+    match entries.iter().position(|entry| entry_markdown(entry).is_none()) {
+        Some(index) => Err(Error::Invalid {
+            path: path.to_string(),
+            message: format!("entry {} names no markdown file", index + 1),
+        }),
+        None => Ok(entries),
+    }
+}
+
+/// Listed by `order`, keeping list order for projects without one.
+fn sort_projects(projects: &mut [ProjectInfo]) {
+    projects.sort_by_key(|project| project.order.unwrap_or(i64::MAX));
+}
+
+/// Projects from the list alone, before their markdown and its front matter has loaded.
+pub fn projects_from_entries(path: &str, entries: &[Value]) -> Result<Vec<ProjectInfo>> {
+    let mut projects = entries.iter()
+        .map(|entry| merge_project(entry, entry_markdown(entry).unwrap_or_default(), ""))
+        .collect::<std::result::Result<Vec<ProjectInfo>, String>>()
+        .map_err(|message| Error::Invalid { path: path.to_string(), message })?;
+    sort_projects(&mut projects);
+    Ok(projects)
+}
+
+/// Entries to build the project list from: those of `projects.json`, or
+/// without a project list, the markdown files found under `projects/`.
+pub fn project_list_entries(terminal_state: &TerminalState) -> Vec<Value> {
+    match terminal_state.project_entries.is_empty() {
+        true => content::list_files(PROJECTS_DIR).into_iter()
+            .filter_map(|path| path.strip_prefix(&format!("{}/", PROJECTS_DIR)).map(String::from))
+            .filter(|markdown| markdown.ends_with(".md"))
+            .map(Value::String)
+            .collect(),
+        false => terminal_state.project_entries.clone(),
+    }
+}
+
+/// Where the markdown of a project list entry is loaded from.
+pub fn entry_markdown_path(entry: &Value) -> String {
+    format!("{}/{}", PROJECTS_DIR, entry_markdown(entry).unwrap_or_default())
+}
+
+/// Builds a project from its entry and its loaded markdown's front matter.
+/// The markdown is kept, so opening the project doesn't load it again.
+pub fn project_from_markdown(terminal_state: &mut TerminalState, entry: &Value, bytes: Vec<u8>) -> Result<ProjectInfo> {
+    let path = entry_markdown_path(entry);
+    let text = String::from_utf8(bytes).map_err(|source| Error::Decode { path: path.clone(), source })?;
+    let project = merge_project(entry, entry_markdown(entry).unwrap_or_default(), &text).map_err(|message| Error::Invalid { path: path.clone(), message })?;
+    terminal_state.project_files.insert(&path, text.into_bytes());
+    Ok(project)
+}
+
+/// Builds a project from its entry alone, for markdown that was skipped. Opening
+/// it says the markdown couldn't be loaded.
+pub fn project_without_markdown(entry: &Value) -> Option<ProjectInfo> {
+    merge_project(entry, entry_markdown(entry).unwrap_or_default(), "").ok()
+}
+
+pub fn set_projects(terminal_state: &mut TerminalState, mut projects: Vec<ProjectInfo>) {
+    sort_projects(&mut projects);
+    terminal_state.projects = projects;
+}
 
-    let mut projects_panel = CellPanel::from_strings(&projects_str, 
-        PROJECT_SIDE_WIDTH - 2,
-        TERM_HEIGHT - PROJECT_ART_HEIGHT - 1, 
-        TERM_WIDTH - PROJECT_SIDE_WIDTH + 1, 
-        PROJECT_ART_HEIGHT );
+/// Loads every project's markdown one after another and builds the project
+/// list from its front matter and the project list entry. Markdown that fails
+/// to load or whose front matter is broken is skipped and reported. The
+/// loading screen does the same with every file loading at once.
+pub async fn load_projects(terminal_state: &mut TerminalState) -> Vec<Error> {
+    let mut projects = Vec::new();
+    let mut errors = Vec::new();
+    for entry in &project_list_entries(terminal_state) {
+        let path = entry_markdown_path(entry);
+        let result = content::load_file(&path).await
+            .map_err(|source| Error::Load { path: path.clone(), source })
+            .and_then(|bytes| project_from_markdown(terminal_state, entry, bytes));
 
-    if let Some(cell_line) = projects_panel.cells.get_mut(0) {
-        for cell in cell_line.iter_mut() {
-            cell.background_color = Some(&WHITE);
+        match result {
+            Ok(project) => projects.push(project),
+            Err(error) => {
+                terminal_state.skipped_files.insert(path);
+                projects.extend(project_without_markdown(entry));
+                errors.push(error);
+            }
         }
-        cell_line.insert(0, Cell::new(' '));
-        cell_line.insert(0, Cell::new('>'));
-    }
-
-    projects_panel.box_color = Some(&WHITE);
-    projects_panel.fit_buffer();
-
-    let cell_project_panels = vec![
-        about_panel,
-        art_panel,
-        projects_panel
-    ];
-    
-    //let first_project_str = &projects_str[0].clone();
-
-    terminal_state.cell_buffer = write_cell_panels_with_border(&cell_project_panels, TERM_WIDTH, TERM_HEIGHT);
-    terminal_state.sub_state = TermSubState::Projects { 
-        selected_project_index: 0, 
-        project_about_scroll: 0, 
-        main_focus: false, 
-        cell_panels: cell_project_panels,
+    }
+
+    set_projects(terminal_state, projects);
+    errors
+}
+
+/// Status, dates, languages and tags on one line, or None when the project lists none of them.
+pub fn project_details(project: &ProjectInfo) -> Option<String> {
+    let mut details = Vec::new();
+    match project.status {
+        Some(ProjectStatus::Active) => details.push(String::from("active")),
+        Some(ProjectStatus::Archived) => details.push(String::from("archived")),
+        None => (),
+    }
+    match (&project.start_date, &project.end_date) {
+        (Some(start), Some(end)) => details.push(format!("{} to {}", start, end)),
+        (Some(start), None) => details.push(format!("since {}", start)),
+        (None, Some(end)) => details.push(format!("until {}", end)),
+        (None, None) => (),
+    }
+    if !project.languages.is_empty() {
+        details.push(project.languages.join(", "));
+    }
+    if !project.tags.is_empty() {
+        details.push(project.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<String>>().join(" "));
+    }
+
+    (!details.is_empty()).then(|| details.join(" | "))
+}
+
+/// Links a project can be opened at: its blog page first, then its named links.
+pub fn project_links(project: &ProjectInfo) -> Vec<ProjectLink> {
+    let page = project.url.as_ref().map(|url| ProjectLink {
+        name: String::from("Project page"),
+        url: match url.starts_with("http://") || url.starts_with("https://") {
+            true => url.clone(),
+            false => format!("{}{}", PROJECT_PAGE_BASE_URL, url),
+        },
+    });
+    page.into_iter().chain(project.links.iter().cloned()).collect()
+}
+
+/// Opens the project's only link, or asks which one to open when it has several.
+pub fn open_project_links(terminal_state: &mut TerminalState, project_index: usize) {
+    let project = &terminal_state.projects[project_index];
+    let links = project_links(project);
+    match links.as_slice() {
+        [] => terminal_state.overlay.toast(&format!("{} has no links", project.name)),
+        [link] => {
+            open_url(&link.url);
+            terminal_state.overlay.toast(&format!("Opening {}...", link.name));
+        }
+        _ => {
+            let mut buttons: Vec<&str> = links.iter().map(|link| link.name.as_str()).collect();
+            buttons.push("Cancel");
+            let dialog = Dialog::new(DialogKind::ProjectLinks { project_index }, "links", &format!("Open a link for {}?", project.name), &buttons);
+            terminal_state.overlay.show_dialog(dialog);
+        }
+    }
+}
+
+/// Opens the link picked in the links dialog. The last button cancels.
+pub fn open_project_link(terminal_state: &mut TerminalState, project_index: usize, link_index: usize) {
+    let Some(project) = terminal_state.projects.get(project_index) else {
+        return;
+    };
+    if let Some(link) = project_links(project).get(link_index) {
+        open_url(&link.url);
+        terminal_state.overlay.toast(&format!("Opening {}...", link.name));
+    }
+}
+
+/// Opens the projects screen. Stays on the current screen when no projects were loaded.
+pub fn setup_projects(terminal_state: &mut TerminalState) -> Result<()> {
+    if terminal_state.projects.is_empty() {
+        terminal_state.overlay.toast("No projects to show");
+        return Ok(());
+    }
+
+    let projects_str: Vec<String> = terminal_state.projects.iter().map(|project: &ProjectInfo| project.name.clone()).collect();
+
+    let project = &terminal_state.projects[0];
+    let rects = project_layout(terminal_state.term_width, false).compute(terminal_state.term_width, terminal_state.term_height);
+
+    let art_rect = rects[ART_PANEL_INDEX];
+    let art_panel = CellPanel::from_strings(project.ascii_art.first().map_or(&[], |lines| lines.as_slice()), art_rect.width, art_rect.height, art_rect.offset_x, art_rect.offset_y);
+
+    let projects_rect = rects[PROJECTS_PANEL_INDEX];
+    let projects_panel = CellPanel::from_strings(&projects_str, projects_rect.width, projects_rect.height, projects_rect.offset_x, projects_rect.offset_y);
+
+    terminal_state.sub_state = TermSubState::Projects {
+        selected_project_index: 0,
+        project_about_scroll: 0,
+        main_focus: false,
+        search: None,
+        cell_panels: vec![
+            CellPanel::default(),
+            art_panel,
+            projects_panel,
+            CellPanel::default(),
+        ],
     };
+
+    update_project_buffer(terminal_state)
 }
 
-pub async fn update_project_buffer( 
-    terminal_state: &mut TerminalState, 
-) {
-    if let TermSubState::Projects { selected_project_index, project_about_scroll, ref main_focus, ref mut cell_panels, .. } = terminal_state.sub_state {
-        
+/// Redraws the projects screen and starts loading the selected project's
+/// markdown in the background if needed, showing a note in its place until it
+/// arrives. When loading fails the screen is still drawn and the error is
+/// returned so it can be retried or skipped.
+pub fn update_project_buffer(
+    terminal_state: &mut TerminalState,
+) -> Result<()> {
+    let (term_width, term_height) = (terminal_state.term_width, terminal_state.term_height);
+
+    if let TermSubState::Projects { selected_project_index, project_about_scroll, ref main_focus, ref search, ref mut cell_panels } = terminal_state.sub_state {
+
         let selected_project = &terminal_state.projects[selected_project_index];
-        
-        if let None = terminal_state.loaded_projects.get(&selected_project.markdown) {
-            let file_data = load_file(&format!("projects/{}", &selected_project.markdown)).await.expect("");
-            let text = String::from_utf8(file_data).expect("Could not parse markdown text");
-            terminal_state.loaded_projects.insert(selected_project.markdown.clone(), text);
+
+        let markdown_path = project_markdown_path(selected_project);
+        let mut errors = Vec::new();
+        if !terminal_state.skipped_files.contains(&markdown_path) {
+            let project_files = &mut terminal_state.project_files;
+            project_files.request(&markdown_path);
+            if project_files.get(&markdown_path).is_some_and(|bytes| std::str::from_utf8(bytes).is_err()) {
+                if let Some(Err(source)) = project_files.take(&markdown_path).map(String::from_utf8) {
+                    errors.push(Error::Decode { path: markdown_path.clone(), source });
+                }
+            }
+            errors.extend(project_files.take_error(&markdown_path));
         }
 
+        let placeholder = match terminal_state.project_files.is_loading(&markdown_path) {
+            true => format!("# {}\n\nLoading `{}`...", selected_project.name, markdown_path),
+            false => format!("# {}\n\nCould not load `{}`.", selected_project.name, markdown_path),
+        };
+        let image_paths: Vec<(String, String)> = image_paths(loaded_text(&terminal_state.project_files, &markdown_path).unwrap_or(&placeholder)).into_iter()
+            .map(|written_path| (written_path.to_string(), project_image_path(written_path)))
+            .filter(|(_, path)| !terminal_state.skipped_files.contains(path))
+            .collect();
+        for (_, path) in &image_paths {
+            if let Err(error) = load_project_image(&mut terminal_state.project_files, &mut terminal_state.project_images, path) {
+                errors.push(error);
+            }
+        }
+        // Only the selected project is worth waiting for when scrolling quickly through the list.
+        let wanted: Vec<&str> = std::iter::once(markdown_path.as_str()).chain(image_paths.iter().map(|(_, path)| path.as_str())).collect();
+        terminal_state.project_files.cancel_all_except(&wanted);
+        let images: HashMap<&str, &Image> = image_paths.iter()
+            .filter_map(|(written_path, path)| Some((written_path.as_str(), terminal_state.project_images.get(path)?)))
+            .collect();
 
-        let markdown = &terminal_state.loaded_projects[&selected_project.markdown];
+        // Failed files aren't tried again until the visitor picks retry. The
+        // first failure is returned, any others get their own dialog.
+        terminal_state.skipped_files.extend(errors.iter().map(|error| error.path().to_string()));
+        let mut errors = errors.into_iter();
+        let load_result = errors.next().map_or(Ok(()), Err);
+        for error in errors {
+            terminal_state.overlay.show_load_error(&error);
+        }
+        let markdown = loaded_text(&terminal_state.project_files, &markdown_path).unwrap_or(&placeholder);
 
-        let mut about_panel = render_markdown(markdown, TERM_WIDTH - PROJECT_SIDE_WIDTH - 1, TERM_HEIGHT - 2);
-        about_panel.offset_y = 1;
-        about_panel.offset_x = 1;
-        about_panel.index = project_about_scroll;
-        about_panel.fit_buffer();
+        let details = project_details(selected_project);
+        let layout = project_layout(term_width, details.is_some());
+        layout.apply(cell_panels, term_width, term_height);
+
+        let about_rect = layout.compute(term_width, term_height)[ABOUT_PANEL_INDEX];
 
-        cell_panels[ABOUT_PANEL_INDEX] = about_panel;
+        // The markdown is only rendered again when it or the panel size changed.
+        // Otherwise the panel on screen is kept and just scrolled.
+        let cached_about = terminal_state.render_cache.get_or_render(&markdown_path, markdown, &images, about_rect.width, about_rect.height);
+        let about_panel = &mut cell_panels[ABOUT_PANEL_INDEX];
+        if about_panel.render_id != cached_about.render_id {
+            // Keep scrolling smoothly from where the old panel was, unless it showed another project.
+            let scroll_position = match about_panel.title.as_ref() == Some(&selected_project.markdown) {
+                true => about_panel.scroll_position,
+                false => project_about_scroll as f32,
+            };
+            *about_panel = cached_about.clone();
+            about_panel.scroll_position = scroll_position;
+        }
+        about_rect.apply(about_panel);
+        about_panel.index = project_about_scroll;
 
         if let Some(projects_panel) = cell_panels.get_mut(PROJECTS_PANEL_INDEX) {
 
             projects_panel.cells.iter_mut().for_each(|cell_line| {
-                if cell_line[0].char == '>' {
+                if cell_line.first().is_some_and(|cell| cell.char == '>') {
                     cell_line.remove(0);
                     cell_line.remove(0);
-                    
+
                     cell_line.iter_mut().for_each(|cell| {
                         cell.background_color = None;
                     })
@@ -126,25 +480,53 @@ pub async fn update_project_buffer(
             projects_panel.fit_buffer();
         }
 
+        // A newly selected project's art starts from its first frame.
+        let animation = &mut terminal_state.art_animation;
+        if animation.project_index != Some(selected_project_index) {
+            *animation = ArtAnimation { project_index: Some(selected_project_index), ..Default::default() };
+        }
+        animation.frame %= selected_project.ascii_art.len().max(1);
         if let Some(art_panel) = cell_panels.get_mut(ART_PANEL_INDEX) {
-            art_panel.update_from_strings(&terminal_state.projects[selected_project_index].ascii_art);
+            art_panel.update_from_cells(art_cells(selected_project, animation.frame));
         }
 
-        match main_focus {
-            true => {
-                cell_panels[PROJECTS_PANEL_INDEX].box_color = None; 
-                cell_panels[ABOUT_PANEL_INDEX].box_color = Some(&WHITE);
-                terminal_state.cell_buffer = write_cell_panels_with_border(&cell_panels, TERM_WIDTH, TERM_HEIGHT);
-            },
-            false => {
-                cell_panels[ABOUT_PANEL_INDEX].box_color = None;
-                cell_panels[PROJECTS_PANEL_INDEX].box_color = Some(&WHITE);
-                terminal_state.cell_buffer = write_cell_panels_with_border(&cell_panels, TERM_WIDTH, TERM_HEIGHT);
+        match &details {
+            Some(details) => {
+                cell_panels[DETAILS_PANEL_INDEX].update_from_strings(std::slice::from_ref(details));
+                cell_panels[DETAILS_PANEL_INDEX].title = Some(String::from("details"));
             }
+            // Emptied so selection and scrolling don't find it where it last was.
+            None => cell_panels[DETAILS_PANEL_INDEX] = CellPanel::default(),
+        }
+
+        cell_panels[ABOUT_PANEL_INDEX].title = Some(selected_project.markdown.clone());
+        cell_panels[PROJECTS_PANEL_INDEX].title = Some(match search {
+            Some(query) => format!("/{}", query),
+            None => String::from("projects"),
+        });
 
+        cell_panels[ABOUT_PANEL_INDEX].focused = *main_focus;
+        cell_panels[PROJECTS_PANEL_INDEX].focused = !*main_focus;
+        for panel in cell_panels.iter_mut() {
+            panel.border_style = match panel.focused {
+                true => terminal_state.border_style.focused(),
+                false => terminal_state.border_style,
+            };
         }
+
+        terminal_state.cell_buffer = write_project_panels(cell_panels, term_width, term_height);
+        load_result
     }
     else {
         panic!("Update project buffer should only be called if sub_state is project");
     }
 }
+
+/// Indices of the projects whose name contains `query`, ignoring case.
+pub fn matching_projects(projects: &[ProjectInfo], query: &str) -> Vec<usize> {
+    let query = query.to_lowercase();
+    projects.iter().enumerate()
+        .filter(|(_, project)| project.name.to_lowercase().contains(&query))
+        .map(|(index, _)| index)
+        .collect()
+}
@@ -0,0 +1,59 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use macroquad::texture::Image;
+
+use crate::{markdown_renderer::render_markdown, models::CellPanel};
+
+struct RenderedDocument {
+    text_hash: u64,
+    panel: CellPanel,
+}
+
+/// Markdown documents rendered and fitted to a panel size, so moving around a
+/// screen doesn't render them again. Colours are left as the theme-neutral
+/// ones cells always hold and mapped when drawing, so a theme change keeps
+/// these valid.
+#[derive(Default)]
+pub struct RenderCache {
+    documents: HashMap<(String, usize, usize), RenderedDocument>,
+    next_render_id: u64,
+}
+
+/// Tells apart texts, and which of their images had arrived when rendered.
+fn text_hash(text: &str, images: &HashMap<&str, &Image>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    let mut image_paths: Vec<&&str> = images.keys().collect();
+    image_paths.sort();
+    image_paths.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl RenderCache {
+    /// `text` rendered into a `width` by `height` panel, rendering it only if
+    /// this document isn't cached at that size or its text changed. The
+    /// panel's `render_id` tells renders apart, so a panel already on screen
+    /// can be kept as it is when it matches.
+    pub fn get_or_render(&mut self, document: &str, text: &str, images: &HashMap<&str, &Image>, width: usize, height: usize) -> &CellPanel {
+        let text_hash = text_hash(text, images);
+        let key = (document.to_string(), width, height);
+
+        if self.documents.get(&key).is_none_or(|rendered| rendered.text_hash != text_hash) {
+            // Sizes the window has moved away from aren't coming back soon.
+            self.documents.retain(|(cached_document, ..), _| cached_document != document);
+
+            let mut panel = render_markdown(text, images, width, height);
+            panel.width = width;
+            panel.height = height;
+            panel.fit_buffer();
+            panel.render_id = Some(self.next_render_id);
+            self.next_render_id += 1;
+            self.documents.insert(key.clone(), RenderedDocument { text_hash, panel });
+        }
+
+        &self.documents[&key].panel
+    }
+}
@@ -1,29 +1,50 @@
-use macroquad::prelude::*;
-use crate::{models::{Cell, TermSubState, TerminalState}, utils::print_cells};
+use std::collections::HashMap;
 
-pub async fn setup_resume(terminal_state: &mut TerminalState) {
+use crate::error::Result;
+use crate::models::{TermSubState, TerminalState};
+use crate::utils::{load_text, write_cell_panels_with_border};
+
+pub const RESUME_PATH: &str = "resume.md";
+
+/// Opens the resume, loading it first if needed. The current screen stays up if it can't be loaded.
+pub async fn setup_resume(terminal_state: &mut TerminalState) -> Result<()> {
     log::info!("Start of resume setup");
-    const RESUME_W : usize = 80;
-    const RESUME_H : usize = 20;
 
-    terminal_state.cell_buffer = vec![vec![Cell::default(); RESUME_W]; RESUME_H];
-    log::info!("Attempting to download resume md");
-    let mark_down_text = String::from_utf8(load_file("resume.md").await.expect("Could not load resume")).expect("Could not decode resume");
-    log::info!("Downloaded resume md");
-    let mut markdown_panel = crate::markdown_renderer::render_markdown(&mark_down_text, RESUME_W, RESUME_H);
-    log::info!("Rendered Markdown");
+    if terminal_state.resume_markdown.is_none() {
+        log::info!("Attempting to download resume md");
+        let mark_down_text = load_text(RESUME_PATH).await?;
+        log::info!("Downloaded resume md");
+        terminal_state.resume_markdown = Some(mark_down_text);
+    }
 
+    terminal_state.sub_state = TermSubState::Resume(Default::default());
+    layout_resume(terminal_state);
+    Ok(())
+}
 
-    markdown_panel.fit_buffer();
-    markdown_panel.write_to_buffer(&mut terminal_state.cell_buffer);
+/// Renders the resume to the current grid size, keeping the scroll position.
+pub fn layout_resume(terminal_state: &mut TerminalState) {
+    let (resume_w, resume_h) = (terminal_state.term_width, terminal_state.term_height);
 
-    print_cells(&markdown_panel.cells);
+    if let (TermSubState::Resume(ref mut markdown_panel), Some(mark_down_text)) = (&mut terminal_state.sub_state, &terminal_state.resume_markdown) {
+        let index = markdown_panel.index;
+        *markdown_panel = terminal_state.render_cache.get_or_render(RESUME_PATH, mark_down_text, &HashMap::new(), resume_w - 2, resume_h - 2).clone();
+
+        markdown_panel.offset_x = 1;
+        markdown_panel.offset_y = 1;
+        markdown_panel.title = Some(String::from("resume.md"));
+        markdown_panel.focused = true;
+        markdown_panel.border_style = terminal_state.border_style.focused();
+
+        markdown_panel.index = usize::min(index, markdown_panel.max_index());
+        markdown_panel.scroll_position = markdown_panel.index as f32;
+    }
 
-    terminal_state.sub_state = TermSubState::Resume(markdown_panel);
+    update_resume_buffer(terminal_state);
 }
 
 pub fn update_resume_buffer(terminal_state: &mut TerminalState) {
-    if let TermSubState::Resume(ref mut markdown_panel) = terminal_state.sub_state {
-        markdown_panel.write_to_buffer(&mut terminal_state.cell_buffer);
+    if let TermSubState::Resume(ref markdown_panel) = terminal_state.sub_state {
+        terminal_state.cell_buffer = write_cell_panels_with_border(std::slice::from_ref(markdown_panel), terminal_state.term_width, terminal_state.term_height);
     }
 }
@@ -0,0 +1,213 @@
+use macroquad::prelude::*;
+
+use crate::{
+    clipboard::copy_to_clipboard,
+    input::{is_ctrl_down, is_shift_down},
+    models::{Cell, CellPanel, TerminalState},
+};
+
+/// A run of cells in reading order between where the selection started and
+/// where it currently ends, both inclusive. Positions are `(x, y)` buffer cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub anchor: (usize, usize),
+    pub head: (usize, usize),
+}
+
+impl Selection {
+    pub fn new(cell: (usize, usize)) -> Self {
+        Selection { anchor: cell, head: cell }
+    }
+
+    /// Start and end of the selection with the earlier cell first.
+    pub fn ordered(&self) -> ((usize, usize), (usize, usize)) {
+        let (anchor_x, anchor_y) = self.anchor;
+        let (head_x, head_y) = self.head;
+        if (anchor_y, anchor_x) <= (head_y, head_x) {
+            (self.anchor, self.head)
+        }
+        else {
+            (self.head, self.anchor)
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        let ((start_x, start_y), (end_x, end_y)) = self.ordered();
+        (start_y, start_x) <= (y, x) && (y, x) <= (end_y, end_x)
+    }
+}
+
+/// Joins rows of text into lines. Rows that were soft wrapped are glued to the
+/// next one, keeping a single space if they ended on a word boundary, and
+/// every other row has its trailing spaces trimmed.
+fn join_rows(rows: impl IntoIterator<Item = (String, bool)>) -> String {
+    let mut text = String::new();
+    for (row, wrapped) in rows {
+        let trimmed = row.trim_end();
+        text.push_str(trimmed);
+        match wrapped {
+            true if trimmed.len() != row.len() => text.push(' '),
+            true => (),
+            false => text.push('\n'),
+        }
+    }
+    text.trim_end().to_string()
+}
+
+fn cells_to_string(cells: &[Cell]) -> String {
+    cells.iter().map(|cell| cell.char).collect()
+}
+
+/// Whether row `y` of the buffer shows a soft wrapped line of `panel`.
+fn is_row_wrapped(panel: &CellPanel, y: usize) -> bool {
+    y.checked_sub(panel.offset_y)
+        .filter(|row| *row < panel.height)
+        .and_then(|row| panel.fitted_wrapped.get(panel.index + row))
+        .copied()
+        .unwrap_or(false)
+}
+
+fn panel_at(panels: &[CellPanel], (x, y): (usize, usize)) -> Option<&CellPanel> {
+    panels.iter().rev().find(|panel| {
+        (panel.offset_x..panel.offset_x + panel.width).contains(&x)
+            && (panel.offset_y..panel.offset_y + panel.height).contains(&y)
+    })
+}
+
+/// Text under the current selection. A selection that starts inside a panel
+/// only takes text from that panel's columns.
+pub fn selected_text(terminal_state: &TerminalState) -> Option<String> {
+    let selection = terminal_state.selection?;
+    let ((start_x, start_y), (end_x, end_y)) = selection.ordered();
+    let panel = panel_at(terminal_state.sub_state.panels(), selection.anchor);
+    let (min_x, max_x) = match panel {
+        Some(panel) => (panel.offset_x, panel.offset_x + panel.width),
+        None => (0, terminal_state.cell_buffer.first().map_or(0, |line| line.len())),
+    };
+
+    let rows = (start_y..=end_y).filter_map(|y| {
+        let line = terminal_state.cell_buffer.get(y)?;
+        let from = if y == start_y { start_x } else { min_x }.clamp(min_x, max_x);
+        let to = if y == end_y { end_x + 1 } else { max_x }.clamp(from, max_x.min(line.len()));
+        let wrapped = y != end_y && panel.is_some_and(|panel| is_row_wrapped(panel, y));
+        Some((cells_to_string(&line[from..to]), wrapped))
+    });
+
+    Some(join_rows(rows))
+}
+
+/// All of the panel's text, including what is scrolled out of view.
+pub fn panel_text(panel: &CellPanel) -> String {
+    join_rows(panel.fitted_buffer.iter().zip(panel.fitted_wrapped.iter()).map(|(line, wrapped)| {
+        (cells_to_string(line), *wrapped)
+    }))
+}
+
+/// Shift+arrows grow the selection from the top left of the focused panel, Ctrl+C
+/// copies the selection, or the focused panel when nothing is selected, and
+/// Ctrl+Shift+C always copies the focused panel.
+pub fn handle_selection_keys(terminal_state: &mut TerminalState) {
+    if is_shift_down() && !is_ctrl_down() {
+        let (dx, dy): (isize, isize) = match () {
+            _ if is_key_pressed(KeyCode::Left) => (-1, 0),
+            _ if is_key_pressed(KeyCode::Right) => (1, 0),
+            _ if is_key_pressed(KeyCode::Up) => (0, -1),
+            _ if is_key_pressed(KeyCode::Down) => (0, 1),
+            _ => (0, 0),
+        };
+
+        if (dx, dy) != (0, 0) {
+            extend_selection(terminal_state, dx, dy);
+        }
+    }
+
+    if is_ctrl_down() && is_key_pressed(KeyCode::C) {
+        let text = match is_shift_down() {
+            true => None,
+            false => selected_text(terminal_state),
+        }.or_else(|| terminal_state.sub_state.focused_panel().map(panel_text));
+
+        if let Some(text) = text.filter(|text| !text.is_empty()) {
+            copy_to_clipboard(&text);
+            info!("Copied {} characters", text.len());
+            terminal_state.overlay.toast("Copied to clipboard");
+        }
+    }
+}
+
+fn extend_selection(terminal_state: &mut TerminalState, dx: isize, dy: isize) {
+    let focused_origin = terminal_state.sub_state.focused_panel().map(|panel| (panel.offset_x, panel.offset_y));
+    let mut selection = match terminal_state.selection {
+        Some(selection) => selection,
+        None => {
+            terminal_state.selection = Some(Selection::new(focused_origin.unwrap_or((0, 0))));
+            return;
+        }
+    };
+
+    let (min_x, min_y, max_x, max_y) = match panel_at(terminal_state.sub_state.panels(), selection.anchor) {
+        Some(panel) => (panel.offset_x, panel.offset_y, panel.offset_x + panel.width, panel.offset_y + panel.height),
+        None => (0, 0, terminal_state.cell_buffer.first().map_or(0, |line| line.len()), terminal_state.cell_buffer.len()),
+    };
+
+    let (head_x, head_y) = selection.head;
+    selection.head = (
+        head_x.saturating_add_signed(dx).clamp(min_x, max_x.saturating_sub(1)),
+        head_y.saturating_add_signed(dy).clamp(min_y, max_y.saturating_sub(1)),
+    );
+    terminal_state.selection = Some(selection);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::TermSubState, utils::generate_cell_line};
+
+    fn rows(rows: &[(&str, bool)]) -> Vec<(String, bool)> {
+        rows.iter().map(|(row, wrapped)| (row.to_string(), *wrapped)).collect()
+    }
+
+    #[test]
+    fn wrapped_rows_are_rejoined_and_hard_breaks_kept() {
+        assert_eq!(join_rows(rows(&[("abcdef", true), ("ghi  ", false), ("jkl", false)])), "abcdefghi\njkl");
+        assert_eq!(join_rows(rows(&[("one  ", false), ("", false), ("two", false)])), "one\n\ntwo");
+    }
+
+    #[test]
+    fn a_wrap_on_a_space_keeps_one_space() {
+        assert_eq!(join_rows(rows(&[("one two ", true), ("three   ", true), ("four", false)])), "one two three four");
+    }
+
+    /// A resume panel over columns 2 to 7 of a buffer with other text either side.
+    fn state_with_panel(selection: Selection) -> TerminalState {
+        let lines = ["##hello ##", "##world ##", "##again ##"];
+        let panel = CellPanel {
+            fitted_wrapped: vec![true, false, false],
+            width: 6,
+            height: 3,
+            offset_x: 2,
+            ..Default::default()
+        };
+        TerminalState {
+            cell_buffer: lines.iter().map(|line| generate_cell_line(line)).collect(),
+            selection: Some(selection),
+            sub_state: TermSubState::Resume(panel),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn selection_inside_a_panel_is_clipped_to_its_columns() {
+        let terminal_state = state_with_panel(Selection { anchor: (3, 0), head: (4, 2) });
+        assert_eq!(selected_text(&terminal_state).as_deref(), Some("ello world\naga"));
+
+        let backwards = state_with_panel(Selection { anchor: (4, 2), head: (3, 0) });
+        assert_eq!(selected_text(&backwards), selected_text(&terminal_state));
+    }
+
+    #[test]
+    fn selection_outside_panels_takes_whole_rows() {
+        let terminal_state = state_with_panel(Selection { anchor: (0, 1), head: (9, 2) });
+        assert_eq!(selected_text(&terminal_state).as_deref(), Some("##world ##\n##again ##"));
+    }
+}
@@ -1,9 +1,8 @@
 use macroquad::math::Rect;
-use crate::TermSubState;
 
 
 
-pub const LOAD_TEMPLATE: [&'static str; 10] = [
+pub const LOAD_TEMPLATE: [&str; 10] = [
 "▄████▄   ▄▄▄       ███▄ ▄███▓ ▄▄▄▄    █    ██  ▄████▄   ██░ ██  ▄▄▄           ▓█████▄ ▓█████  ██▒   █▓",
 "▒██▀ ▀█  ▒████▄    ▓██▒▀█▀ ██▒▓█████▄  ██  ▓██▒▒██▀ ▀█  ▓██░ ██▒▒████▄         ▒██▀ ██▌▓█   ▀ ▓██░   █▒",
 "▒▓█    ▄ ▒██  ▀█▄  ▓██    ▓██░▒██▒ ▄██▓██  ▒██░▒▓█    ▄ ▒██▀▀██░▒██  ▀█▄       ░██   █▌▒███    ▓██  █▒░",
@@ -16,7 +15,15 @@ pub const LOAD_TEMPLATE: [&'static str; 10] = [
 "░                                   ░          ░                            ░   ░                  ░   ",
 ];
 
-pub const MAIN_TEMPLATE: [&'static str; 11] = [
+/// Used instead of `LOAD_TEMPLATE` when the window is too narrow to show it at a
+/// readable font size.
+pub const LOAD_TEMPLATE_COMPACT: [&str; 3] = [
+"▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄▄",
+"█ CAMBUCHA.DEV █",
+"▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀",
+];
+
+pub const MAIN_TEMPLATE: [&str; 11] = [
 "Welcome to CAMBUCHA.DEV (TM) TermLink",
 "Select One",
 "|==========================================|",
@@ -32,18 +39,16 @@ pub const MAIN_TEMPLATE: [&'static str; 11] = [
 
 pub fn generate_highlight_box(index: usize) -> Option<Rect> {
     let coords = MAIN_TEMPLATE.iter().enumerate().find_map(|line| {
-        match line.1.find(&index.to_string()) {
-            Some(val) => Some((val, line.0)),
-            None => None
-        }
+        line.1.find(&index.to_string()).map(|val| (val, line.0))
     })?;
 
     let count = MAIN_TEMPLATE[coords.1][coords.0..].find(" ")?;
 
-    Some(Rect { x: (coords.0 ) as f32, y: (coords.1 ) as f32, w: count as f32, h: 1 as f32 } )
+    Some(Rect { x: (coords.0 ) as f32, y: (coords.1 ) as f32, w: count as f32, h: 1f32 } )
 }
 
-pub const SAND_SPINNER: [&'static str;35] = [
+#[allow(dead_code)]
+pub const SAND_SPINNER: [&str;35] = [
     		"⠁",
 			"⠂",
 			"⠄",
@@ -82,16 +87,6 @@ pub const SAND_SPINNER: [&'static str;35] = [
 ];
 
 
-pub const BALLOON_SPINNER: [&'static str; 7] = [
-    ".",
-    "o",
-    "O",
-    "°",
-    "O",
-    "o",
-    "."
-];
-
 pub const BALLOON_SPINNER_CHARS: [char; 7] = [
     '.',
     'o',
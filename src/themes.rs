@@ -0,0 +1,151 @@
+use macroquad::prelude::*;
+
+/// Colours the screen is drawn with. Cells are built with the phosphor
+/// theme's colours, and the active theme swaps those for its own when drawing.
+/// Content can ask for any of them by `PaletteKey`.
+pub struct Theme {
+    pub name: &'static str,
+    pub foreground: Color,
+    pub highlight: Color,
+    pub background: Color,
+    pub accent: Color,
+    pub dim: Color,
+}
+
+/// The phosphor theme's accent and dim, which cells hold for those keys.
+const PHOSPHOR_ACCENT: Color = Color::new(0.95, 0.85, 0.25, 1.0);
+const PHOSPHOR_DIM: Color = Color::new(0.0, 0.45, 0.1, 1.0);
+
+/// A colour of the active theme, named by a single character in art colour masks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteKey {
+    Foreground,
+    Highlight,
+    Background,
+    Accent,
+    Dim,
+}
+
+impl PaletteKey {
+    pub const ALL: [PaletteKey; 5] = [PaletteKey::Foreground, PaletteKey::Highlight, PaletteKey::Background, PaletteKey::Accent, PaletteKey::Dim];
+
+    pub fn char(self) -> char {
+        match self {
+            PaletteKey::Foreground => 'f',
+            PaletteKey::Highlight => 'h',
+            PaletteKey::Background => 'b',
+            PaletteKey::Accent => 'a',
+            PaletteKey::Dim => 'd',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<PaletteKey> {
+        PaletteKey::ALL.into_iter().find(|key| key.char() == c)
+    }
+
+    /// The colour cells hold for this key, which `Theme::map` swaps for the
+    /// active theme's when drawing.
+    pub fn cell_color(self) -> &'static Color {
+        match self {
+            PaletteKey::Foreground => &GREEN,
+            PaletteKey::Highlight => &WHITE,
+            PaletteKey::Background => &DARKGRAY,
+            PaletteKey::Accent => &PHOSPHOR_ACCENT,
+            PaletteKey::Dim => &PHOSPHOR_DIM,
+        }
+    }
+
+    /// The key whose phosphor colour is closest to `color`, used by `image_to_ascii`.
+    #[allow(dead_code)]
+    pub fn nearest(color: &Color) -> PaletteKey {
+        let distance = |key: &PaletteKey| {
+            let key_color = key.cell_color();
+            (key_color.r - color.r).powi(2) + (key_color.g - color.g).powi(2) + (key_color.b - color.b).powi(2)
+        };
+        PaletteKey::ALL.into_iter().min_by(|a, b| distance(a).total_cmp(&distance(b))).unwrap_or(PaletteKey::Foreground)
+    }
+}
+
+pub const THEMES: [Theme; 4] = [
+    Theme {
+        name: "phosphor",
+        foreground: GREEN,
+        highlight: WHITE,
+        background: DARKGRAY,
+        accent: PHOSPHOR_ACCENT,
+        dim: PHOSPHOR_DIM,
+    },
+    Theme {
+        name: "amber",
+        foreground: Color::new(1.0, 0.69, 0.0, 1.0),
+        highlight: Color::new(1.0, 0.9, 0.7, 1.0),
+        background: Color::new(0.12, 0.08, 0.02, 1.0),
+        accent: Color::new(1.0, 0.4, 0.1, 1.0),
+        dim: Color::new(0.55, 0.38, 0.0, 1.0),
+    },
+    Theme {
+        name: "ice",
+        foreground: Color::new(0.45, 0.85, 1.0, 1.0),
+        highlight: Color::new(0.9, 0.97, 1.0, 1.0),
+        background: Color::new(0.04, 0.08, 0.14, 1.0),
+        accent: Color::new(0.7, 0.55, 1.0, 1.0),
+        dim: Color::new(0.2, 0.4, 0.55, 1.0),
+    },
+    Theme {
+        name: "paper",
+        foreground: Color::new(0.15, 0.15, 0.15, 1.0),
+        highlight: Color::new(0.55, 0.55, 0.5, 1.0),
+        background: Color::new(0.93, 0.91, 0.84, 1.0),
+        accent: Color::new(0.7, 0.15, 0.1, 1.0),
+        dim: Color::new(0.6, 0.6, 0.57, 1.0),
+    },
+];
+
+impl Theme {
+    pub fn map(&self, color: Color) -> Color {
+        if color == GREEN {
+            self.foreground
+        }
+        else if color == WHITE {
+            self.highlight
+        }
+        else if color == DARKGRAY {
+            self.background
+        }
+        else if color == PHOSPHOR_ACCENT {
+            self.accent
+        }
+        else if color == PHOSPHOR_DIM {
+            self.dim
+        }
+        else {
+            color
+        }
+    }
+}
+
+pub fn theme(index: usize) -> &'static Theme {
+    &THEMES[index % THEMES.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_keys_round_trip_through_their_char() {
+        for key in PaletteKey::ALL {
+            assert_eq!(PaletteKey::from_char(key.char()), Some(key));
+        }
+        assert_eq!(PaletteKey::from_char(' '), None);
+    }
+
+    #[test]
+    fn nearest_key_matches_cell_colours() {
+        for key in PaletteKey::ALL {
+            assert_eq!(PaletteKey::nearest(key.cell_color()), key);
+        }
+        assert_eq!(PaletteKey::nearest(&Color::new(1.0, 0.8, 0.0, 1.0)), PaletteKey::Accent);
+        assert_eq!(PaletteKey::nearest(&Color::new(0.0, 0.0, 0.0, 1.0)), PaletteKey::Dim);
+    }
+}
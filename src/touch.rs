@@ -0,0 +1,185 @@
+use macroquad::{miniquad::window::dpi_scale, prelude::*};
+
+use crate::{
+    grid::{cell_at_position, MAX_FONT_SCALE, MIN_FONT_SCALE},
+    mouse::{hit_target, HitTarget},
+    models::TerminalState,
+    ui::is_over_controls,
+};
+
+/// How far, in rows, a finger has to move before a touch stops being a tap.
+const DRAG_THRESHOLD: f32 = 0.75;
+
+/// Share of the scroll speed lost every second once the finger is lifted.
+const INERTIA_FRICTION: f32 = 4.0;
+
+/// Inertia stops below this many rows per second.
+const MIN_INERTIA_SPEED: f32 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Swipe {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+enum Gesture {
+    #[default]
+    None,
+    /// A finger is down but has not moved far enough to be anything but a tap.
+    Pending { id: u64, start: Vec2 },
+    Scrolling { id: u64, last: Vec2 },
+    /// A horizontal swipe already fired, the rest of the touch is ignored.
+    Swiped { id: u64 },
+    Pinching { start_distance: f32, start_scale: f32 },
+}
+
+#[derive(Default)]
+pub struct TouchContext {
+    /// Set once the screen has been touched, until a real mouse moves again.
+    pub in_use: bool,
+    pub tapped: Option<HitTarget>,
+    /// Whole rows to scroll this frame, positive moves further down the content.
+    pub scroll_lines: isize,
+    pub swipe: Option<Swipe>,
+    pub font_scale_changed: bool,
+    gesture: Gesture,
+    /// Scroll speed in rows per second, kept going after the finger is lifted.
+    velocity: f32,
+    scroll_remainder: f32,
+    last_touch_position: Vec2,
+}
+
+impl TouchContext {
+    pub fn reset(&mut self) {
+        self.tapped = None;
+        self.scroll_lines = 0;
+        self.swipe = None;
+        self.font_scale_changed = false;
+    }
+}
+
+/// Turns this frame's touches into taps, scrolling with inertia, horizontal
+/// swipes and pinch zoom. Touch positions are in physical pixels, like the grid.
+pub fn handle_touch(screen_w: f32, screen_h: f32, touch_context: &mut TouchContext, terminal_state: &mut TerminalState) {
+    let frame_time = get_frame_time();
+    let touches = touches();
+    let active: Vec<&Touch> = touches.iter()
+        .filter(|touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+        .collect();
+
+    if let Some(touch) = touches.first() {
+        touch_context.in_use = true;
+        touch_context.last_touch_position = touch.position;
+    }
+    else if touch_context.in_use && Vec2::from(mouse_position()) * dpi_scale() != touch_context.last_touch_position {
+        touch_context.in_use = false;
+    }
+
+    if let [first, second, ..] = active[..] {
+        let distance = first.position.distance(second.position);
+        match touch_context.gesture {
+            Gesture::Pinching { start_distance, start_scale } if start_distance > 0f32 => {
+                let font_scale = (start_scale * distance / start_distance).clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+                if font_scale != terminal_state.font_scale {
+                    terminal_state.font_scale = font_scale;
+                    touch_context.font_scale_changed = true;
+                }
+            }
+            _ => {
+                touch_context.gesture = Gesture::Pinching { start_distance: distance, start_scale: terminal_state.font_scale };
+                touch_context.velocity = 0f32;
+            }
+        }
+        return;
+    }
+
+    let row_height = terminal_state.font_size.max(1f32);
+    for touch in &touches {
+        match (touch_context.gesture, touch.phase) {
+            (Gesture::None, TouchPhase::Started) => {
+                touch_context.gesture = Gesture::Pending { id: touch.id, start: touch.position };
+                touch_context.velocity = 0f32;
+                touch_context.scroll_remainder = 0f32;
+            }
+            (Gesture::Pending { id, start }, TouchPhase::Moved) if id == touch.id => {
+                let delta = touch.position - start;
+                if delta.length() < DRAG_THRESHOLD * row_height {
+                    continue;
+                }
+
+                if delta.y.abs() >= delta.x.abs() {
+                    touch_context.gesture = Gesture::Scrolling { id, last: start };
+                    scroll_to(touch_context, touch.position, row_height, frame_time);
+                }
+                else {
+                    touch_context.gesture = Gesture::Swiped { id };
+                    touch_context.swipe = Some(match delta.x < 0f32 {
+                        true => Swipe::Left,
+                        false => Swipe::Right,
+                    });
+                }
+            }
+            (Gesture::Scrolling { id, .. }, TouchPhase::Moved) if id == touch.id => {
+                scroll_to(touch_context, touch.position, row_height, frame_time);
+            }
+            // Holding still before lifting the finger should not fling.
+            (Gesture::Scrolling { id, .. }, TouchPhase::Stationary) if id == touch.id => {
+                touch_context.velocity *= 0.5;
+            }
+            (Gesture::Pending { id, .. }, TouchPhase::Ended) if id == touch.id => {
+                touch_context.gesture = Gesture::None;
+                touch_context.tapped = tap_target(screen_w, screen_h, touch.position, terminal_state);
+            }
+            // Started and ended within the same frame.
+            (Gesture::None, TouchPhase::Ended) => {
+                touch_context.tapped = tap_target(screen_w, screen_h, touch.position, terminal_state);
+            }
+            (Gesture::Pending { id, .. } | Gesture::Scrolling { id, .. } | Gesture::Swiped { id }, TouchPhase::Ended | TouchPhase::Cancelled) if id == touch.id => {
+                touch_context.gesture = Gesture::None;
+            }
+            _ => (),
+        }
+    }
+
+    // The last finger of a pinch was lifted.
+    if active.is_empty() && matches!(touch_context.gesture, Gesture::Pinching { .. }) {
+        touch_context.gesture = Gesture::None;
+    }
+
+    if matches!(touch_context.gesture, Gesture::None) && touch_context.velocity != 0f32 {
+        touch_context.scroll_remainder += touch_context.velocity * frame_time;
+        touch_context.velocity *= (-INERTIA_FRICTION * frame_time).exp();
+        if touch_context.velocity.abs() < MIN_INERTIA_SPEED {
+            touch_context.velocity = 0f32;
+        }
+    }
+
+    touch_context.scroll_lines = touch_context.scroll_remainder.trunc() as isize;
+    touch_context.scroll_remainder = touch_context.scroll_remainder.fract();
+}
+
+fn tap_target(screen_w: f32, screen_h: f32, position: Vec2, terminal_state: &TerminalState) -> Option<HitTarget> {
+    if is_over_controls(screen_w, screen_h, position / dpi_scale(), false, terminal_state) {
+        return None;
+    }
+
+    let cell = cell_at_position(terminal_state, position.x, position.y)?;
+    hit_target(terminal_state, cell).map(|(target, _)| target)
+}
+
+/// Scrolls by how far the finger moved since the last frame. Moving the finger
+/// up moves further down the content, like dragging a page.
+fn scroll_to(touch_context: &mut TouchContext, position: Vec2, row_height: f32, frame_time: f32) {
+    let Gesture::Scrolling { id, last } = touch_context.gesture else {
+        return;
+    };
+
+    let rows = (last.y - position.y) / row_height;
+    touch_context.scroll_remainder += rows;
+    if frame_time > 0f32 {
+        // Smooth the speed over a few frames so one jittery frame does not decide the fling.
+        touch_context.velocity = touch_context.velocity * 0.5 + rows / frame_time * 0.5;
+    }
+    touch_context.gesture = Gesture::Scrolling { id, last: position };
+}
@@ -1,6 +1,6 @@
-use macroquad::{color::{Color, DARKGREEN, GREEN, WHITE}, math::{RectOffset, Vec2}, text::Font, ui::{hash, root_ui, Skin}};
+use macroquad::{color::{Color, DARKGREEN, GREEN, WHITE}, math::{Rect, RectOffset, Vec2}, text::Font, ui::{hash, root_ui, Skin, StyleBuilder}};
 
-use crate::{models::TerminalState, opener::open_url};
+use crate::{models::{TermSubState, TerminalState}, projects::{open_project_links, project_links}};
 
 #[derive(Default)]
 pub struct UiContext {
@@ -30,13 +30,18 @@ const MARGIN: f32 = FONT_SIZE / 4.0;
 const TRANSPARENT: Color = Color::new(0f32, 0f32, 0f32, 0f32);
 const TRANSPARENT_WHITE: Color = Color::new(1f32, 1f32, 1f32, 0.25f32);
 
-pub fn create_ui_skin(font: &Font) -> Skin {
+/// Uses `font` when the terminal font loaded, macroquad's own font otherwise.
+fn with_optional_font(style_builder: StyleBuilder, font: Option<&Font>) -> StyleBuilder {
+    match font {
+        Some(font) => style_builder.with_font(font).unwrap(),
+        None => style_builder,
+    }
+}
+
+pub fn create_ui_skin(font: Option<&Font>) -> Skin {
 
 
-    let button_style = root_ui()
-        .style_builder()
-        .with_font(font)
-        .unwrap()
+    let button_style = with_optional_font(root_ui().style_builder(), font)
         .text_color(GREEN)
         .font_size(FONT_SIZE as u16)
         .color(TRANSPARENT)
@@ -50,11 +55,8 @@ pub fn create_ui_skin(font: &Font) -> Skin {
     }
 }
 
-pub fn button_ui_skin(font: &Font) -> Skin {
-    let button_style = root_ui()
-        .style_builder()
-        .with_font(font)
-        .unwrap()
+pub fn button_ui_skin(font: Option<&Font>) -> Skin {
+    let button_style = with_optional_font(root_ui().style_builder(), font)
         .text_color(WHITE)
         .font_size(40)
         .color(DARKGREEN)
@@ -74,48 +76,45 @@ pub fn handle_ui(screen_w: f32,
     ui_context: &mut UiContext, 
     ui_skin: &Skin, 
     button_skin: &Skin,
+    dpad_visible: bool,
     terminal_state: &mut TerminalState) {
     root_ui().push_skin(ui_skin);
-    root_ui().group(hash!(), Vec2::new(screen_w - 1f32, screen_h - 1f32), |ui| {
-        // UP Button
-        
-        let up_button_position = generate_pos(2f32, 2f32, screen_w, screen_h);
-        ui_context.up_pressed = ui.button(Some(up_button_position), "↑");
-
-        // ENTER Button
-        let enter_button_position = generate_pos(1f32, 2f32, screen_w, screen_h);
-        ui_context.enter_pressed = ui.button(Some(enter_button_position), "↵"); 
-
-        // DOWN Button
-        let down_button_position = generate_pos(2f32, 1f32, screen_w, screen_h);
-        ui_context.down_pressed = ui.button(Some(down_button_position), "↓");
-
-        let left_button_position = generate_pos(3f32, 1f32, screen_w, screen_h);
-        ui_context.left_pressed = ui.button(Some(left_button_position), "←");
-
-        let right_button_position =  generate_pos(1f32, 1f32, screen_w, screen_h);
-        ui_context.right_pressed = ui.button(Some(right_button_position), "→");
-
-        let back_button_position = generate_pos(3f32, 2f32, screen_w, screen_h);
-        ui_context.back_pressed = ui.button(Some(back_button_position), "Ø");
-    });
+    // Touch screens use gestures instead, so the d-pad only gets in the way there.
+    if dpad_visible {
+        root_ui().group(hash!(), Vec2::new(screen_w - 1f32, screen_h - 1f32), |ui| {
+            // UP Button
+            
+            let up_button_position = generate_pos(2f32, 2f32, screen_w, screen_h);
+            ui_context.up_pressed = ui.button(Some(up_button_position), "↑");
+
+            // ENTER Button
+            let enter_button_position = generate_pos(1f32, 2f32, screen_w, screen_h);
+            ui_context.enter_pressed = ui.button(Some(enter_button_position), "↵"); 
+
+            // DOWN Button
+            let down_button_position = generate_pos(2f32, 1f32, screen_w, screen_h);
+            ui_context.down_pressed = ui.button(Some(down_button_position), "↓");
+
+            let left_button_position = generate_pos(3f32, 1f32, screen_w, screen_h);
+            ui_context.left_pressed = ui.button(Some(left_button_position), "←");
+
+            let right_button_position =  generate_pos(1f32, 1f32, screen_w, screen_h);
+            ui_context.right_pressed = ui.button(Some(right_button_position), "→");
+
+            let back_button_position = generate_pos(3f32, 2f32, screen_w, screen_h);
+            ui_context.back_pressed = ui.button(Some(back_button_position), "Ø");
+        });
+    }
 
     root_ui().push_skin(button_skin);
 
-    match terminal_state.sub_state {
-        crate::models::TermSubState::Resume(_) => {
-            if center_button(screen_w, screen_h, "Download") {
-
-            }
-        }
-        crate::models::TermSubState::Projects{selected_project_index, ..}  => {
-            if center_button(screen_w, screen_h, "Project Page") {
-                let selected_url = format!("https://blog.cambucha.dev/projects/{}", &terminal_state.projects[selected_project_index].url);
-                open_url(&selected_url);
-            }
+    let center_button_pressed = center_button_label(terminal_state)
+        .is_some_and(|label| center_button(screen_w, screen_h, label));
 
+    if let TermSubState::Projects{selected_project_index, ..} = terminal_state.sub_state {
+        if center_button_pressed {
+            open_project_links(terminal_state, selected_project_index);
         }
-        _ => ()
     }
 
     root_ui().pop_skin();
@@ -123,9 +122,38 @@ pub fn handle_ui(screen_w: f32,
 }
 
 fn center_button(screen_w: f32, screen_h: f32, label: &str) -> bool {
+    let rect = center_button_rect(screen_w, screen_h, label);
+    root_ui().button(rect.point(), label)
+}
+
+fn center_button_rect(screen_w: f32, screen_h: f32, label: &str) -> Rect {
     let width = screen_w / 2f32 - (FONT_SIZE / 2f32) * (label.len() as f32 / 2f32 + 0.5f32);
     let height = screen_h - FONT_SIZE * 2f32;
-    return root_ui().button(Vec2::new(width, height), label);
+    Rect::new(width, height, (FONT_SIZE / 2f32) * (label.len() as f32 + 1f32) + MARGIN * 2.0, FONT_SIZE + MARGIN * 2.0)
+}
+
+fn center_button_label(terminal_state: &TerminalState) -> Option<&'static str> {
+    match terminal_state.sub_state {
+        TermSubState::Resume(_) => Some("Download"),
+        TermSubState::Projects { selected_project_index, .. } => match project_links(&terminal_state.projects[selected_project_index]).len() {
+            0 => None,
+            1 => Some("Project Page"),
+            _ => Some("Links"),
+        },
+        _ => None,
+    }
+}
+
+/// Whether `position` is over one of the on-screen buttons, so the terminal
+/// can ignore clicks that the buttons already handle.
+pub fn is_over_controls(screen_w: f32, screen_h: f32, position: Vec2, dpad_visible: bool, terminal_state: &TerminalState) -> bool {
+    let dpad_corner = generate_pos(3f32, 2f32, screen_w, screen_h);
+    if dpad_visible && position.x >= dpad_corner.x && position.y >= dpad_corner.y {
+        return true;
+    }
+
+    center_button_label(terminal_state)
+        .is_some_and(|label| center_button_rect(screen_w, screen_h, label).contains(position))
 }
 
 fn generate_pos(buttons_from_right: f32, buttons_from_bottom: f32, screen_w: f32, screen_h: f32) -> Vec2 {
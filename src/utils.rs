@@ -1,65 +1,209 @@
 
 use macroquad::prelude::*;
-use crate::TerminalState;
-use crate::models::{Cell, CellPanel};
+use crate::{content, TerminalState};
+use crate::error::{Error, Result};
+use crate::models::{BorderStyle, Cell, CellPanel, FontType};
 
+const SCROLLBAR_THUMB: char = '█';
 
+
+/// Box drawing lines leaving a border cell, with the style each one was drawn in.
+#[derive(Clone, Copy, Debug, Default)]
+struct BorderArms {
+    up: Option<BorderStyle>,
+    down: Option<BorderStyle>,
+    left: Option<BorderStyle>,
+    right: Option<BorderStyle>,
+}
+
+impl BorderArms {
+    fn is_empty(&self) -> bool {
+        self.up.is_none() && self.down.is_none() && self.left.is_none() && self.right.is_none()
+    }
+
+    /// Adds `other` on top of these arms. Arms already present are kept unless
+    /// `replace` is set, which focused panels use so their outline stays whole.
+    fn merge(&mut self, other: BorderArms, replace: bool) {
+        let merge_arm = |arm: &mut Option<BorderStyle>, new_arm: Option<BorderStyle>| {
+            if new_arm.is_some() && (arm.is_none() || replace) {
+                *arm = new_arm;
+            }
+        };
+        merge_arm(&mut self.up, other.up);
+        merge_arm(&mut self.down, other.down);
+        merge_arm(&mut self.left, other.left);
+        merge_arm(&mut self.right, other.right);
+    }
+}
+
+/// Writes the panels and their one cell borders into a new buffer. Later panels
+/// are drawn over earlier ones, borders that meet are joined, and anything that
+/// falls outside the terminal is clipped.
 pub fn write_cell_panels_with_border(
     panels: &[CellPanel], 
     term_width: usize, 
     term_height: usize) -> Vec<Vec<Cell>> {
 
     let mut cell_buffer: Vec<Vec<Cell>> = vec![vec![Cell::default(); term_width]; term_height];
-    let mut border_map: Vec<Vec<bool>> = vec![vec![false;term_width]; term_height];
+    let mut border_map: Vec<Vec<BorderArms>> = vec![vec![BorderArms::default(); term_width]; term_height];
 
     for panel in panels {
-        assert!(panel.offset_y > 0 
-            && panel.offset_y + panel.height < term_height
-            && panel.offset_x > 0
-            && panel.offset_x + panel.width < term_width, 
-        "Panel size: {:?} offset: {:?} does not fit inside terminal with border", (panel.width, panel.height), (panel.offset_x, panel.offset_y));
-
-        for x in (panel.offset_x - 1)..(panel.offset_x + panel.width + 1) {
-
-            border_map[panel.offset_y - 1][x] = true;
-            border_map[panel.offset_y + panel.height][x] = true;
-            if cell_buffer[panel.offset_y - 1][x].foreground_color == &GREEN {
-                cell_buffer[panel.offset_y - 1][x].foreground_color = panel.box_color.unwrap_or(&GREEN);
-            }
-            if cell_buffer[panel.offset_y + panel.height][x].foreground_color == &GREEN {
-                cell_buffer[panel.offset_y + panel.height][x].foreground_color = panel.box_color.unwrap_or(&GREEN);
+        let box_color = panel_border_color(panel);
+        let style = Some(panel.border_style);
+
+        let left = panel.offset_x as isize - 1;
+        let right = (panel.offset_x + panel.width) as isize;
+        let top = panel.offset_y as isize - 1;
+        let bottom = (panel.offset_y + panel.height) as isize;
+
+        for y in top.max(0)..=bottom.min(term_height as isize - 1) {
+            for x in left.max(0)..=right.min(term_width as isize - 1) {
+                let on_vertical_edge = x == left || x == right;
+                let on_horizontal_edge = y == top || y == bottom;
+                let (cell_x, cell_y) = (x as usize, y as usize);
+
+                if !on_vertical_edge && !on_horizontal_edge {
+                    // The panel hides whatever borders it is drawn over.
+                    border_map[cell_y][cell_x] = BorderArms::default();
+                    continue;
+                }
+
+                let arms = BorderArms {
+                    up: style.filter(|_| on_vertical_edge && y > top),
+                    down: style.filter(|_| on_vertical_edge && y < bottom),
+                    left: style.filter(|_| on_horizontal_edge && x > left),
+                    right: style.filter(|_| on_horizontal_edge && x < right),
+                };
+                border_map[cell_y][cell_x].merge(arms, panel.focused);
+
+                if cell_buffer[cell_y][cell_x].foreground_color == &GREEN {
+                    cell_buffer[cell_y][cell_x].foreground_color = box_color;
+                }
             }
         }
 
-        for y in (panel.offset_y - 1)..(panel.offset_y + panel.height + 1) {
-            border_map[y][panel.offset_x - 1] = true;
-            border_map[y][panel.offset_x + panel.width] = true;
+        panel.write_to_buffer(&mut cell_buffer);
+    }
+
+    for y in 0..term_height {
+        for x in 0..term_width {
+            let mut arms = border_map[y][x];
+            if arms.is_empty() {
+                continue;
+            }
 
-            if cell_buffer[y][panel.offset_x - 1].foreground_color == &GREEN {
-                cell_buffer[y][panel.offset_x - 1].foreground_color = panel.box_color.unwrap_or(&GREEN);
+            // Drop arms that point at a neighbour with nothing to join, such as
+            // a border covered by a panel drawn later.
+            if y > 0 && border_map[y - 1][x].down.is_none() {
+                arms.up = None;
             }
-            if cell_buffer[y][panel.offset_x + panel.width].foreground_color == &GREEN {
-                cell_buffer[y][panel.offset_x + panel.width].foreground_color = panel.box_color.unwrap_or(&GREEN);
+            if y < term_height - 1 && border_map[y + 1][x].up.is_none() {
+                arms.down = None;
             }
+            if x > 0 && border_map[y][x - 1].right.is_none() {
+                arms.left = None;
+            }
+            if x < term_width - 1 && border_map[y][x + 1].left.is_none() {
+                arms.right = None;
+            }
+
+            cell_buffer[y][x].char = parse_cell_from_neighbors(arms.up, arms.down, arms.left, arms.right);
         }
+    }
 
-        panel.write_to_buffer(&mut cell_buffer);
+    for (panel_index, panel) in panels.iter().enumerate() {
+        let panels_above = &panels[panel_index + 1..];
+        write_panel_scrollbar(panel, panels_above, &mut cell_buffer);
+        write_panel_title(panel, panels_above, &mut cell_buffer);
     }
 
-    for y in 0..term_height {
-        for x in 0..term_width {
-            if border_map[y][x] {
-                cell_buffer[y][x].char = parse_cell_from_neighbors(
-                    y > 0 && border_map[y-1][x],
-                    y < term_height - 1 && border_map[y+1][x], 
-                    x > 0 && border_map[y][x-1], 
-                    x < term_width - 1 && border_map[y][x+1], 
-                );
+    cell_buffer
+}
+
+/// Draws `panels` with their borders on top of an existing buffer, leaving
+/// everything around them as it was.
+pub fn write_overlay_panels(panels: &[CellPanel], cell_buffer: &mut [Vec<Cell>]) {
+    let term_height = cell_buffer.len();
+    let term_width = cell_buffer.first().map_or(0, |line| line.len());
+    let overlay = write_cell_panels_with_border(panels, term_width, term_height);
+
+    for (y, (line, overlay_line)) in cell_buffer.iter_mut().zip(overlay).enumerate() {
+        for (x, (cell, overlay_cell)) in line.iter_mut().zip(overlay_line).enumerate() {
+            if is_covered(panels, x, y) {
+                *cell = overlay_cell;
             }
         }
     }
+}
 
-    cell_buffer
+/// Whether the cell is under one of `panels`, border included.
+fn is_covered(panels: &[CellPanel], x: usize, y: usize) -> bool {
+    panels.iter().any(|panel| {
+        x + 1 >= panel.offset_x && x <= panel.offset_x + panel.width
+            && y + 1 >= panel.offset_y && y <= panel.offset_y + panel.height
+    })
+}
+
+fn panel_border_color(panel: &CellPanel) -> &'static Color {
+    match (panel.box_color, panel.focused) {
+        (Some(box_color), _) => box_color,
+        (None, true) => &WHITE,
+        (None, false) => &GREEN,
+    }
+}
+
+/// Writes the panel title into its top border, just after the left corner.
+/// Focused panels get their title in reverse video.
+fn write_panel_title(panel: &CellPanel, panels_above: &[CellPanel], cell_buffer: &mut [Vec<Cell>]) {
+    let Some(title) = &panel.title else {
+        return;
+    };
+    let Some(y) = panel.offset_y.checked_sub(1) else {
+        return;
+    };
+    let Some(row) = cell_buffer.get_mut(y) else {
+        return;
+    };
+
+    let max_chars = panel.width.saturating_sub(2);
+    let title_chars: Vec<char> = format!(" {} ", title).chars().take(max_chars).collect();
+    let (foreground_color, background_color): (&'static Color, Option<&'static Color>) = match panel.focused {
+        true => (&BLACK, Some(panel_border_color(panel))),
+        false => (panel_border_color(panel), None),
+    };
+
+    let cells = row.iter_mut().enumerate().skip(panel.offset_x + 1);
+    for ((x, cell), c) in cells.zip(title_chars) {
+        if is_covered(panels_above, x, y) {
+            continue;
+        }
+        *cell = Cell {
+            char: c,
+            foreground_color,
+            background_color,
+            font_type: FontType::Default,
+        };
+    }
+}
+
+/// Draws a scrollbar thumb over the right border when the panel has more
+/// lines than it can show.
+fn write_panel_scrollbar(panel: &CellPanel, panels_above: &[CellPanel], cell_buffer: &mut [Vec<Cell>]) {
+    let line_count = panel.fitted_buffer.len();
+    if panel.height == 0 || line_count <= panel.height {
+        return;
+    }
+
+    let max_index = line_count - panel.height;
+    let thumb_height = usize::max(1, panel.height * panel.height / line_count);
+    let thumb_start = usize::min(panel.index, max_index) * (panel.height - thumb_height) / max_index;
+
+    let x = panel.offset_x + panel.width;
+    for (y, row) in cell_buffer.iter_mut().enumerate().skip(panel.offset_y + thumb_start).take(thumb_height) {
+        if let Some(cell) = row.get_mut(x).filter(|_| !is_covered(panels_above, x, y)) {
+            cell.char = SCROLLBAR_THUMB;
+        }
+    }
 }
 
 pub fn generate_cell_line(string: &str) -> Vec<Cell> {
@@ -95,89 +239,74 @@ pub fn reset_all_highlights(terminal_state: &mut TerminalState) {
     });
 }
 
-pub fn parse_cell_from_neighbors(up: bool,
-    down:bool, left: bool, right: bool) -> char {
-    match (up, down, left, right) {
-        (true, true, false, false) => '┃',
-        (false, false, true, true) => '━',
-        (true, true, false, true) => '┣',
-        (true, true, true, false) => '┫',
-        (true, false, true, true) => '┻',
-        (false, true, true, true) => '┳',
-        (true, true, true, true) => '╋',
-        (false, true, false, true) => '┏',
-        (false, true, true, false) => '┓',
-        (true, false, false, true) => '┗',
-        (true, false, true, false) => '┛',
-        (u, d, l, r) => panic!("up: {}, down: {}, left: {}, right: {}", u, d, l , r),
-
-    }
-}
-
-fn fit_strings_to_size<'a>(
-    width: usize, 
-    height: usize, 
-    line_index: usize, 
-    strings: &'a Vec<String>) -> Vec<&'a str> {
-
-    let test : Vec<&str> =strings[line_index..]
-        .iter()
-        .flat_map(|line| line.split("\n"))
-        .flat_map(|line| {
-
-            let mut temp : Vec<&str> = Vec::new();
-            if line.len() > width {
-                let split_line = line.split_at(width);
-                temp.push(split_line.0);
-                temp.push(split_line.1);
-            } else {
-                temp.push(line);
-            }
-            temp
-
-        })
-        .take(height)
-        .collect();
-
-
-    test
+/// Every combination of light and heavy arms, indexed by `up * 27 + down * 9 + left * 3 + right`
+/// where 0 is no arm, 1 is light and 2 is heavy.
+const LIGHT_HEAVY_GLYPHS: [char; 81] = [
+    '·', '╶', '╺', '╴', '─', '╼', '╸', '╾', '━',
+    '╷', '┌', '┍', '┐', '┬', '┮', '┑', '┭', '┯',
+    '╻', '┎', '┏', '┒', '┰', '┲', '┓', '┱', '┳',
+    '╵', '└', '┕', '┘', '┴', '┶', '┙', '┵', '┷',
+    '│', '├', '┝', '┤', '┼', '┾', '┥', '┽', '┿',
+    '╽', '┟', '┢', '┧', '╁', '╆', '┪', '╅', '╈',
+    '╹', '┖', '┗', '┚', '┸', '┺', '┛', '┹', '┻',
+    '╿', '┞', '┡', '┦', '╀', '╄', '┩', '╃', '╇',
+    '┃', '┠', '┣', '┨', '╂', '╊', '┫', '╉', '╋',
+];
+
+/// Same indexing as `LIGHT_HEAVY_GLYPHS` with 1 as single and 2 as double. Unicode
+/// has no glyph for a line that changes between single and double as it passes
+/// through a cell, so those draw the whole line double.
+const DOUBLE_GLYPHS: [char; 81] = [
+    '·', '╶', '═', '╴', '─', '═', '═', '═', '═',
+    '╷', '┌', '╒', '┐', '┬', '╤', '╕', '╤', '╤',
+    '║', '╓', '╔', '╖', '╥', '╦', '╗', '╦', '╦',
+    '╵', '└', '╘', '┘', '┴', '╧', '╛', '╧', '╧',
+    '│', '├', '╞', '┤', '┼', '╪', '╡', '╪', '╪',
+    '║', '╟', '╠', '╢', '╫', '╬', '╣', '╬', '╬',
+    '║', '╙', '╚', '╜', '╨', '╩', '╝', '╩', '╩',
+    '║', '╟', '╠', '╢', '╫', '╬', '╣', '╬', '╬',
+    '║', '╟', '╠', '╢', '╫', '╬', '╣', '╬', '╬',
+];
+
+fn glyph_index(arms: [Option<BorderStyle>; 4], weight: fn(BorderStyle) -> usize) -> usize {
+    arms.iter().fold(0, |index, arm| index * 3 + arm.map_or(0, weight))
 }
 
-fn write_strs_to_cell_buffer(
-    offset_x: usize,
-    offset_y: usize,
-    parsed: &Vec<&str>,
-    char_buffer: &mut Vec<Vec<Cell>>) {
-    assert!(parsed.len() > 0 && parsed[0].len() > 0);
-    assert!(char_buffer.len() > parsed.len() + offset_y && 
-            char_buffer[0].len() > parsed[0].len() + offset_x);
-
-
-    parsed.iter().enumerate().for_each(|(line_index, line)| {
-        line.chars().enumerate().for_each(|(char_index, char_val)| {
-            char_buffer[offset_y + line_index][offset_x + char_index].char = char_val;
-        });
-    })
-}
-
-fn write_strs_to_char_buffer(
-    offset_x: usize,
-    offset_y: usize,
-    parsed: &Vec<&str>,
-    char_buffer: &mut Vec<Vec<char>>) {
-    assert!(parsed.len() > 0 && parsed[0].len() > 0);
-    assert!(char_buffer.len() > parsed.len() + offset_y && 
-            char_buffer[0].len() > parsed[0].len() + offset_x);
+/// Picks the box drawing character for a border cell from the lines leaving it.
+/// Every combination has a character, down to a lone dot for a cell with none.
+pub fn parse_cell_from_neighbors(up: Option<BorderStyle>,
+    down: Option<BorderStyle>, left: Option<BorderStyle>, right: Option<BorderStyle>) -> char {
+    let arms = [up, down, left, right];
+
+    if arms.contains(&Some(BorderStyle::Ascii)) {
+        return match (up.is_some() || down.is_some(), left.is_some() || right.is_some()) {
+            (true, false) => '|',
+            (false, true) => '-',
+            _ => '+',
+        };
+    }
 
+    if arms.contains(&Some(BorderStyle::Double)) {
+        return DOUBLE_GLYPHS[glyph_index(arms, |style| match style {
+            BorderStyle::Double => 2,
+            _ => 1,
+        })];
+    }
 
-    parsed.iter().enumerate().for_each(|(line_index, line)| {
-        line.chars().enumerate().for_each(|(char_index, char_val)| {
-            char_buffer[offset_y + line_index][offset_x + char_index] = char_val;
-        });
-    })
+    let rounded = Some(BorderStyle::Rounded);
+    match (up, down, left, right) {
+        (None, Some(_), None, Some(_)) if down == rounded && right == rounded => '╭',
+        (None, Some(_), Some(_), None) if down == rounded && left == rounded => '╮',
+        (Some(_), None, None, Some(_)) if up == rounded && right == rounded => '╰',
+        (Some(_), None, Some(_), None) if up == rounded && left == rounded => '╯',
+        _ => LIGHT_HEAVY_GLYPHS[glyph_index(arms, |style| match style {
+            BorderStyle::Heavy => 2,
+            _ => 1,
+        })],
+    }
 }
 
-pub fn overflow_sub(num:&usize, range: usize) -> usize {
+pub fn overflow_sub(num: &usize, range: usize) -> usize {
     if *num == 0 {
         range - 1
     }
@@ -186,26 +315,94 @@ pub fn overflow_sub(num:&usize, range: usize) -> usize {
     }
 }
 
-pub fn find_substr(cell_line: &Vec<crate::models::Cell>, substr: &str) -> Option<usize> {
+/// Loads a file as text, naming it in the error if it is missing or not UTF-8.
+pub async fn load_text(path: &str) -> Result<String> {
+    let bytes = content::load_file(path).await.map_err(|source| Error::Load { path: path.to_string(), source })?;
+    String::from_utf8(bytes).map_err(|source| Error::Decode { path: path.to_string(), source })
+}
 
-    for cell_index in 0..(cell_line.len() - substr.len()) {
-        let mut found = true;
-        for (char_index, char) in substr.chars().enumerate() {
-            found = found && cell_line[cell_index + char_index].char == char;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIGHT: Option<BorderStyle> = Some(BorderStyle::Light);
+    const HEAVY: Option<BorderStyle> = Some(BorderStyle::Heavy);
+    const DOUBLE: Option<BorderStyle> = Some(BorderStyle::Double);
+    const ROUNDED: Option<BorderStyle> = Some(BorderStyle::Rounded);
+    const ASCII: Option<BorderStyle> = Some(BorderStyle::Ascii);
+
+    #[test]
+    fn light_and_heavy_arms_pick_their_junction() {
+        assert_eq!(parse_cell_from_neighbors(None, None, None, None), '·');
+        assert_eq!(parse_cell_from_neighbors(None, LIGHT, None, LIGHT), '┌');
+        assert_eq!(parse_cell_from_neighbors(LIGHT, LIGHT, LIGHT, LIGHT), '┼');
+        assert_eq!(parse_cell_from_neighbors(HEAVY, HEAVY, HEAVY, HEAVY), '╋');
+        assert_eq!(parse_cell_from_neighbors(None, HEAVY, LIGHT, LIGHT), '┰');
+        assert_eq!(parse_cell_from_neighbors(HEAVY, LIGHT, None, HEAVY), '┡');
+        assert_eq!(parse_cell_from_neighbors(None, None, LIGHT, HEAVY), '╼');
+    }
 
-        if found {
-            return Some(cell_index);
+    #[test]
+    fn double_arms_meeting_single_arms() {
+        assert_eq!(parse_cell_from_neighbors(None, DOUBLE, None, DOUBLE), '╔');
+        assert_eq!(parse_cell_from_neighbors(None, LIGHT, None, DOUBLE), '╒');
+        assert_eq!(parse_cell_from_neighbors(DOUBLE, DOUBLE, LIGHT, None), '╢');
+        assert_eq!(parse_cell_from_neighbors(LIGHT, LIGHT, DOUBLE, DOUBLE), '╪');
+        assert_eq!(parse_cell_from_neighbors(None, DOUBLE, None, None), '║');
+    }
+
+    #[test]
+    fn lines_changing_to_double_are_drawn_double() {
+        assert_eq!(parse_cell_from_neighbors(None, LIGHT, LIGHT, DOUBLE), '╤');
+        assert_eq!(parse_cell_from_neighbors(LIGHT, None, DOUBLE, HEAVY), '╧');
+        assert_eq!(parse_cell_from_neighbors(LIGHT, DOUBLE, None, LIGHT), '╟');
+        assert_eq!(parse_cell_from_neighbors(None, None, LIGHT, DOUBLE), '═');
+    }
+
+    #[test]
+    fn every_double_glyph_keeps_its_arms() {
+        // A glyph has an arm wherever the index asked for one.
+        let has_arm = |glyph: char, direction: usize| match direction {
+            0 => "│└┘├┤┴┼║╘╙╚╛╜╝╞╟╠╡╢╣╧╨╩╪╫╬╵".contains(glyph),
+            1 => "│┌┐├┤┬┼║╒╓╔╕╖╗╞╟╠╡╢╣╤╥╦╪╫╬╷".contains(glyph),
+            2 => "─┐┘┤┬┴┼═╕╖╗╛╜╝╡╢╣╤╥╦╧╨╩╪╫╬╴".contains(glyph),
+            _ => "─┌└├┬┴┼═╒╓╔╘╙╚╞╟╠╤╥╦╧╨╩╪╫╬╶".contains(glyph),
+        };
+        for (index, glyph) in DOUBLE_GLYPHS.iter().enumerate() {
+            for direction in 0..4 {
+                let weight = index / 3usize.pow(3 - direction as u32) % 3;
+                if weight != 0 {
+                    assert!(has_arm(*glyph, direction), "{:?} at {} is missing arm {}", glyph, index, direction);
+                }
+            }
         }
     }
-    None
-}
 
-pub fn print_cells(cell_buffer: &Vec<Vec<Cell>>) {
-    cell_buffer.iter().for_each(|line| {
-        line.iter().for_each(|cell| {
-            print!("{}", cell.char);
-        });
-        print!("\n");
-    })
+    #[test]
+    fn rounded_corners_only_when_both_arms_are_rounded() {
+        assert_eq!(parse_cell_from_neighbors(None, ROUNDED, None, ROUNDED), '╭');
+        assert_eq!(parse_cell_from_neighbors(ROUNDED, None, ROUNDED, None), '╯');
+        assert_eq!(parse_cell_from_neighbors(None, ROUNDED, None, HEAVY), '┍');
+        assert_eq!(parse_cell_from_neighbors(ROUNDED, ROUNDED, None, ROUNDED), '├');
+    }
+
+    #[test]
+    fn any_ascii_arm_draws_ascii() {
+        assert_eq!(parse_cell_from_neighbors(ASCII, ASCII, None, None), '|');
+        assert_eq!(parse_cell_from_neighbors(None, None, ASCII, HEAVY), '-');
+        assert_eq!(parse_cell_from_neighbors(None, ASCII, None, DOUBLE), '+');
+    }
+
+    #[test]
+    fn neighbouring_panels_join_their_shared_border() {
+        let mut left = CellPanel::from_strings(&[], 3, 1, 1, 1);
+        left.border_style = BorderStyle::Light;
+        let mut right = CellPanel::from_strings(&[], 3, 1, 5, 1);
+        right.border_style = BorderStyle::Double;
+
+        let buffer = write_cell_panels_with_border(&[left, right], 9, 3);
+        let lines: Vec<String> = buffer.iter().map(|line| line.iter().map(|cell| cell.char).collect()).collect();
+
+        assert_eq!(lines, ["┌───╤═══╗", "│   │   ║", "└───╧═══╝"]);
+    }
 }
@@ -0,0 +1,18 @@
+/// Whether the window is in front of the user. miniquad has no focus query
+/// and macroquad only acts on its minimise events on Android, so natively the
+/// window always counts as visible.
+#[cfg(not(target_arch="wasm32"))]
+pub fn window_visible() -> bool {
+    true
+}
+
+#[cfg(target_arch="wasm32")]
+extern "C" {
+    fn page_visible() -> u32;
+}
+
+/// Asks the page whether its tab is showing and has focus.
+#[cfg(target_arch="wasm32")]
+pub fn window_visible() -> bool {
+    unsafe { page_visible() != 0 }
+}